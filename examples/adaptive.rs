@@ -0,0 +1,87 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture, object::ObjectDefinition, renderer::PathTracer, shape::Plane,
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const MIN_SAMPLES: usize = 16;
+const MAX_SAMPLES: usize = 1024;
+
+fn main() {
+    // The relative error to refine pixels to can be given.
+    let threshold: f64 = env::args()
+        .nth(1)
+        .map_or(0.05, |arg| arg.parse().expect("Threshold must be a number"));
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6)),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    // The glass sphere throws a caustic and the small light gives sharp, noisy shadows, while
+    // most of the floor converges quickly.
+    let glass = ObjectDefinition {
+        shape: Box::new(Sphere::new(0.8)),
+        material: Material::new_reflective(Vector3::new(1., 1., 1.), 0., 1., 1.5),
+        x: -1.,
+        y: -0.7,
+        z: -6.,
+        ..Default::default()
+    };
+
+    let diffuse = ObjectDefinition {
+        shape: Box::new(Sphere::new(0.8)),
+        material: Material::new_lambertian(Vector3::new(0.2, 0.4, 0.8)),
+        x: 1.2,
+        y: -0.7,
+        z: -6.,
+        ..Default::default()
+    };
+
+    let light = ObjectDefinition {
+        shape: Box::new(Plane::new(0.5, 0.5)),
+        material: Material::new_emissive(Vector3::new(1., 0.95, 0.9), 40.),
+        y: 2.5,
+        z: -6.,
+        rx: TAU / 4.,
+        ..Default::default()
+    };
+
+    let scene = Scene::new(camera, vec![floor, glass, diffuse, light]);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10)
+        .adaptive(MIN_SAMPLES, MAX_SAMPLES)
+        .with_threshold(threshold);
+    let statistics = renderer.render_statistics(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let counts = statistics.sample_counts();
+    let total: f64 = (0..counts.width())
+        .flat_map(|x| (0..counts.height()).map(move |y| (x, y)))
+        .map(|pixel| counts[pixel].x)
+        .sum();
+    println!(
+        "{:.1} samples per pixel on average",
+        total / (counts.width() * counts.height()) as f64
+    );
+
+    let image = statistics.mean().srgb().to_image_u8();
+    image.save("image.png").expect("Could not save image");
+
+    let heatmap = statistics.heatmap().to_image_u8();
+    heatmap.save("heatmap.png").expect("Could not save heatmap");
+}
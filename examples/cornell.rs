@@ -3,6 +3,7 @@ use std::{f64::consts::TAU, time::Instant};
 use path_tracer::{
     aperture::RegularPolygonAperture,
     camera::CameraSettings,
+    filter::GaussianFilter,
     object::ObjectDefinition,
     renderer::RecursiveBDPT,
     shape::{Cuboid, Plane},
@@ -26,11 +27,12 @@ fn main() {
         znear: 1.,
         ..Default::default()
     };
-    let camera = Camera::new(camera_settings, aperture, 2.25);
+    let camera =
+        Camera::new(camera_settings, aperture, 2.25).with_filter(GaussianFilter::default());
 
-    let white_material = Material::new(Vector3::new(1., 1., 1.) * 0.8, 0.5, false);
-    let green_material = Material::new(Vector3::new(0.1, 0.8, 0.1), 0.5, false);
-    let red_material = Material::new(Vector3::new(0.8, 0.1, 0.1), 0.5, false);
+    let white_material = Material::new_lambertian(Vector3::new(1., 1., 1.) * 0.8);
+    let green_material = Material::new_lambertian(Vector3::new(0.1, 0.8, 0.1));
+    let red_material = Material::new_lambertian(Vector3::new(0.8, 0.1, 0.1));
 
     let bottom_plane = ObjectDefinition {
         shape: Box::new(Plane::new(2., 2.)),
@@ -73,7 +75,7 @@ fn main() {
 
     let box_a = ObjectDefinition {
         shape: Box::new(Cuboid::new(0.4, 0.4, 0.4)),
-        material: Material::new(Vector3::new(0.7, 0.8, 0.6), 0.5, false),
+        material: Material::new_lambertian(Vector3::new(0.7, 0.8, 0.6)),
         x: -0.25,
         y: -0.7,
         z: -0.2,
@@ -106,7 +108,8 @@ fn main() {
         shape: Box::new(Plane::new(0.25, 0.25)),
         material: Material::new(Vector3::new(1.0, 1.0, 0.5), 1., true),
         y: 0.995,
-        rx: -TAU / 4.,
+        // Facing down into the box, as emitters are one-sided.
+        rx: TAU / 4.,
         ..Default::default()
     };
 
@@ -4,7 +4,7 @@ use path_tracer::{
     aperture::RegularPolygonAperture,
     camera::CameraSettings,
     object::ObjectDefinition,
-    renderer::RecursiveBDPT,
+    renderer::BDPTRenderer,
     shape::{Cylinder, Plane},
     Camera, Material, Renderer, Scene, Sphere,
 };
@@ -60,7 +60,7 @@ fn main() {
     let scene = Scene::new(camera, vec![bottom_plane, cylinder, top_light]);
 
     let start = Instant::now();
-    let renderer = RecursiveBDPT::new(10).parallel(NUM_SAMPLES);
+    let renderer = BDPTRenderer::new(10).parallel(NUM_SAMPLES);
     let render_buffer = renderer.render(&scene);
 
     //let render_buffer = render_buffer.median_filter(9);
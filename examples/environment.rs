@@ -0,0 +1,88 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture, light::EnvironmentLight, object::ObjectDefinition,
+    renderer::PathTracer, shape::Plane, Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::{Rotation3, Vector3};
+
+const NUM_SAMPLES: usize = 100;
+
+/// A sky that fades from blue to white towards the horizon, with a small bright sun.
+fn procedural_sky() -> EnvironmentLight {
+    let (width, height) = (256, 128);
+    let pixels = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let elevation = 1. - 2. * (y as f64 + 0.5) / height as f64;
+            let sun = (x as f64 - 160.).hypot(y as f64 - 40.) < 2.;
+
+            if sun {
+                Vector3::new(1000., 900., 750.)
+            } else if elevation > 0. {
+                Vector3::new(0.3, 0.5, 1.).lerp(&Vector3::new(0.9, 0.9, 1.), 1. - elevation)
+            } else {
+                Vector3::new(0.2, 0.18, 0.15)
+            }
+        })
+        .collect();
+
+    EnvironmentLight::new(width, height, pixels)
+}
+
+fn main() {
+    // An equirectangular EXR file can be given to light the scene instead of the built-in sky.
+    let environment = match env::args().nth(1) {
+        Some(path) => EnvironmentLight::open(path).expect("Could not load environment"),
+        None => procedural_sky(),
+    }
+    .with_rotation(Rotation3::from_axis_angle(&Vector3::y_axis(), TAU / 8.))
+    .with_intensity(1.);
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6)),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let materials = [
+        Material::new_lambertian(Vector3::new(0.8, 0.3, 0.2)),
+        Material::new(Vector3::new(0.95, 0.95, 0.95), 0.05, false),
+        Material::new_reflective(Vector3::new(1., 1., 1.), 0., 0.5, 1.5),
+    ];
+
+    let mut objects: Vec<_> = materials
+        .into_iter()
+        .enumerate()
+        .map(|(i, material)| ObjectDefinition {
+            shape: Box::new(Sphere::new(0.8)),
+            material,
+            x: i as f64 * 2. - 2.,
+            y: -0.7,
+            z: -6.,
+            ..Default::default()
+        })
+        .collect();
+    objects.push(floor);
+
+    let scene = Scene::with_lights(camera, objects, vec![environment.into()]);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
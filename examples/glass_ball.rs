@@ -2,9 +2,10 @@ use std::{f64::consts::TAU, time::Instant};
 
 use path_tracer::{
     aperture::PinholeAperture,
+    bsdf::Dielectric,
     camera::CameraSettings,
     object::ObjectDefinition,
-    renderer::RecursiveBDPT,
+    renderer::BDPTRenderer,
     shader::Checkerboard,
     shape::{Cuboid, Plane},
     Camera, Material, Renderer, Scene, Sphere,
@@ -45,7 +46,7 @@ fn main() {
 
     let left_cuboid = ObjectDefinition {
         shape: Box::new(Cuboid::new(1., 1., 0.25)),
-        material: Material::new_reflective(Vector3::new(0.9, 0.99, 0.9), 0., 0.9, ior),
+        material: Material::new_bsdf(Dielectric::new(ior, 0.)),
         x: -0.6,
         z: 1.,
         scale: 0.75,
@@ -54,7 +55,7 @@ fn main() {
 
     let right_cuboid = ObjectDefinition {
         shape: Box::new(Cuboid::new(1., 1., 0.25)),
-        material: Material::new_reflective(Vector3::new(0.9, 0.99, 0.9), 0., 0.9, ior),
+        material: Material::new_bsdf(Dielectric::new(ior, 0.)),
         x: 0.6,
         z: 1.,
         rx: TAU / 16.,
@@ -64,7 +65,7 @@ fn main() {
 
     let sphere = ObjectDefinition {
         shape: Box::new(Sphere::new(0.5)),
-        material: Material::new_reflective(Vector3::new(0.9, 0.99, 0.9), 0., 0.9, ior),
+        material: Material::new_bsdf(Dielectric::new(ior, 0.)),
         y: 1.,
         z: 1.,
         ..Default::default()
@@ -72,7 +73,7 @@ fn main() {
 
     let inner_sphere = ObjectDefinition {
         shape: Box::new(Sphere::new(0.5)),
-        material: Material::new_reflective(Vector3::new(1., 1., 1.), 0., 0.9, 1. / ior),
+        material: Material::new_bsdf(Dielectric::new(1. / ior, 0.)),
         y: 1.,
         z: 1.,
         scale: 0.9,
@@ -108,7 +109,7 @@ fn main() {
     );
 
     let start = Instant::now();
-    let renderer = RecursiveBDPT::new(10).parallel(NUM_SAMPLES);
+    let renderer = BDPTRenderer::new(10).parallel(NUM_SAMPLES);
     let render_buffer = renderer.render(&scene);
 
     //let render_buffer = render_buffer.median_filter(9);
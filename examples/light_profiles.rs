@@ -0,0 +1,83 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    emission::{EmissionProfile, IesProfile},
+    object::ObjectDefinition,
+    renderer::PathTracer,
+    shape::Plane,
+    Camera, Emission, Material, Renderer, Scene,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    // An IES file can be given to light the scene with a measured profile instead of a spot.
+    let profile = match env::args().nth(1) {
+        Some(path) => EmissionProfile::ies(IesProfile::open(path).expect("Could not load profile")),
+        None => EmissionProfile::spot(TAU / 16., TAU / 10.),
+    };
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let grey = Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6));
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: grey.clone(),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let back_wall = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 10.)),
+        material: grey,
+        z: -8.,
+        ..Default::default()
+    };
+
+    let warm = Vector3::new(1., 0.85, 0.6);
+
+    // Lights facing down: a diffuse panel, a two-sided panel that also lights the wall above
+    // and a panel with a narrow profile.
+    let lights = [
+        Emission::new(warm, 3.),
+        Emission::new(warm, 3.).two_sided(),
+        Emission::new(warm, 12.).with_profile(profile),
+    ];
+
+    let mut objects: Vec<_> = lights
+        .into_iter()
+        .enumerate()
+        .map(|(i, emission)| ObjectDefinition {
+            shape: Box::new(Plane::new(0.6, 0.6)),
+            material: Material::new_emitter(emission),
+            x: i as f64 * 2.4 - 2.4,
+            y: 1.,
+            z: -6.,
+            rx: TAU / 4.,
+            ..Default::default()
+        })
+        .collect();
+
+    objects.extend([floor, back_wall]);
+
+    let scene = Scene::new(camera, objects);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
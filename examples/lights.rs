@@ -0,0 +1,93 @@
+use std::{f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    light::{DirectionalLight, PointLight},
+    object::ObjectDefinition,
+    renderer::BDPTRenderer,
+    shape::Plane,
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::{Point3, Vector3};
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let grey = Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6));
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: grey.clone(),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let back_wall = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 10.)),
+        material: grey,
+        z: -8.,
+        ..Default::default()
+    };
+
+    let spheres = [
+        Material::new_lambertian(Vector3::new(0.8, 0.2, 0.2)),
+        Material::new(Vector3::new(0.9, 0.9, 0.9), 0.2, false),
+        Material::new_lambertian(Vector3::new(0.2, 0.3, 0.8)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, material)| ObjectDefinition {
+        shape: Box::new(Sphere::new(0.7)),
+        material,
+        x: i as f64 * 2.4 - 2.4,
+        y: -0.8,
+        z: -6.,
+        ..Default::default()
+    });
+
+    let lights = vec![
+        // A low sun with a soft shadow
+        DirectionalLight::new(
+            Vector3::new(1., -0.6, -0.4),
+            Vector3::new(1., 0.9, 0.8),
+            1.5,
+            0.05,
+        )
+        .into(),
+        // A bare bulb above the left sphere
+        PointLight::new(Point3::new(-2.4, 1., -5.), Vector3::new(1., 0.6, 0.3), 2.).into(),
+        // A spot shining down on the right sphere
+        PointLight::spot(
+            Point3::new(2.4, 2., -6.),
+            -Vector3::y(),
+            Vector3::new(0.5, 0.7, 1.),
+            10.,
+            TAU / 24.,
+            TAU / 16.,
+        )
+        .into(),
+    ];
+
+    let mut objects: Vec<_> = spheres.collect();
+    objects.extend([floor, back_wall]);
+
+    let scene = Scene::with_lights(camera, objects, lights);
+
+    let start = Instant::now();
+
+    let renderer = BDPTRenderer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
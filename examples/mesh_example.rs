@@ -0,0 +1,108 @@
+use std::{f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    object::ObjectDefinition,
+    renderer::BDPTRenderer,
+    shape::{Plane, Triangle, TriangleMesh},
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::{Point3, Vector3};
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(300, 300, 55., 1.0, 100.0, aperture, 5.);
+
+    let octahedron_vertices = vec![
+        Point3::new(1., 0., 0.),
+        Point3::new(-1., 0., 0.),
+        Point3::new(0., 1., 0.),
+        Point3::new(0., -1., 0.),
+        Point3::new(0., 0., 1.),
+        Point3::new(0., 0., -1.),
+    ];
+    let octahedron_indices = vec![
+        [0, 2, 4],
+        [2, 1, 4],
+        [1, 3, 4],
+        [3, 0, 4],
+        [2, 0, 5],
+        [1, 2, 5],
+        [3, 1, 5],
+        [0, 3, 5],
+    ];
+
+    let flat_octahedron = ObjectDefinition {
+        shape: Box::new(TriangleMesh::new(
+            octahedron_vertices.clone(),
+            octahedron_indices.clone(),
+        )),
+        x: -1.2,
+        z: -6.,
+        ry: TAU / 16.,
+        material: Material::new(Vector3::new(0.8, 0.3, 0.3), 0.3, false),
+        ..Default::default()
+    };
+
+    let smooth_octahedron = ObjectDefinition {
+        shape: Box::new(TriangleMesh::smooth(
+            octahedron_vertices,
+            octahedron_indices,
+        )),
+        x: 1.2,
+        z: -6.,
+        ry: TAU / 16.,
+        material: Material::new(Vector3::new(0.3, 0.3, 0.8), 0.3, false),
+        ..Default::default()
+    };
+
+    let triangle = ObjectDefinition {
+        shape: Box::new(Triangle::new(
+            Point3::new(-1., -1., 0.),
+            Point3::new(1., -1., 0.),
+            Point3::new(0., 1., 0.),
+        )),
+        y: 1.5,
+        z: -8.,
+        material: Material::new(Vector3::new(0.3, 0.8, 0.3), 0.3, false),
+        ..Default::default()
+    };
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(10., 10.)),
+        y: -1.,
+        z: -6.,
+        rx: TAU / 4.,
+        material: Material::new(Vector3::new(0.8, 0.8, 0.8), 1., false),
+        ..Default::default()
+    };
+
+    let light = ObjectDefinition {
+        shape: Box::new(Sphere::new(1.)),
+        y: 3.,
+        z: -4.,
+        material: Material::new(Vector3::new(1., 1., 1.), 1.0, true),
+        ..Default::default()
+    };
+
+    let scene = Scene::new(
+        camera,
+        vec![flat_octahedron, smooth_octahedron, triangle, floor, light],
+    );
+
+    let start = Instant::now();
+
+    let renderer = BDPTRenderer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -0,0 +1,67 @@
+use std::time::Instant;
+
+use path_tracer::{
+    aperture::PinholeAperture, bsdf::Conductor, object::ObjectDefinition, renderer::PathTracer,
+    shape::Plane, Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(400, 200, 55., 1.0, 100.0, aperture, 5.);
+
+    let metals = [
+        Conductor::gold(0.2),
+        Conductor::copper(0.35),
+        Conductor::silver(0.05),
+        Conductor::aluminium(0.5),
+    ];
+
+    let mut objects: Vec<_> = metals
+        .into_iter()
+        .enumerate()
+        .map(|(i, metal)| ObjectDefinition {
+            shape: Box::new(Sphere::new(1.)),
+            material: Material::new_bsdf(metal),
+            x: i as f64 * 2.2 - 3.3,
+            z: -7.,
+            ..Default::default()
+        })
+        .collect();
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(Vector3::new(0.5, 0.5, 0.5)),
+        y: -1.,
+        rx: (-90f64).to_radians(),
+        ..Default::default()
+    };
+
+    let light = ObjectDefinition {
+        shape: Box::new(Sphere::new(1.)),
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 4.),
+        y: 4.,
+        z: -4.,
+        ..Default::default()
+    };
+
+    objects.extend([floor, light]);
+
+    let scene = Scene::new(camera, objects);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
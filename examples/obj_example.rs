@@ -0,0 +1,56 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture, loader::load_obj, object::ObjectDefinition, renderer::RecursiveBDPT,
+    shape::Plane, Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .expect("Usage: obj_example <path to .obj file>");
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(300, 300, 55., 1.0, 100.0, aperture, 5.);
+
+    let mut objects = load_obj(&path).expect("Could not load obj file");
+    for object in &mut objects {
+        object.z = -4.;
+    }
+
+    objects.push(ObjectDefinition {
+        shape: Box::new(Plane::new(10., 10.)),
+        y: -1.,
+        z: -4.,
+        rx: TAU / 4.,
+        material: Material::new(Vector3::new(0.8, 0.8, 0.8), 1., false),
+        ..Default::default()
+    });
+
+    objects.push(ObjectDefinition {
+        shape: Box::new(Sphere::new(1.)),
+        y: 3.,
+        z: -2.,
+        material: Material::new(Vector3::new(1., 1., 1.), 1.0, true),
+        ..Default::default()
+    });
+
+    let scene = Scene::new(camera, objects);
+
+    let start = Instant::now();
+
+    let renderer = RecursiveBDPT::new(5).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -0,0 +1,134 @@
+use std::time::Instant;
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    object::ObjectDefinition,
+    renderer::PathTracer,
+    shader::{
+        Cellular, Checkerboard, ColorRamp, Fbm, Marble, Mix, Multiply, Perlin, Remap, Simplex,
+        Transform, Triplanar, Turbulence, Wood,
+    },
+    shape::{Cuboid, Plane},
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::{Similarity3, Vector3};
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(600, 300, 55., 1.0, 100.0, aperture, 5.);
+
+    let white = Vector3::new(0.9, 0.9, 0.85);
+    let grey = Vector3::new(0.25, 0.25, 0.3);
+
+    let materials = [
+        Material::new_lambertian(Fbm::new(Perlin::new(1), grey, white, 0.3, 6)),
+        Material::new_lambertian(Turbulence::new(
+            Simplex::new(2),
+            Vector3::new(0.1, 0.2, 0.6),
+            white,
+            0.5,
+            6,
+        )),
+        Material::new_lambertian(Cellular::new(
+            3,
+            Vector3::new(0.8, 0.3, 0.1),
+            Vector3::new(0.1, 0.05, 0.02),
+            0.3,
+        )),
+        Material::new_lambertian(Marble::new(white, grey, 0.5, 2.)),
+        Material::new_lambertian(Wood::new(
+            Vector3::new(0.55, 0.35, 0.18),
+            Vector3::new(0.3, 0.15, 0.06),
+            0.15,
+            0.4,
+        )),
+    ];
+
+    let mut objects: Vec<_> = materials
+        .into_iter()
+        .enumerate()
+        .map(|(i, material)| ObjectDefinition {
+            shape: Box::new(Sphere::new(0.8)),
+            material,
+            x: i as f64 * 1.8 - 3.6,
+            z: -7.,
+            ..Default::default()
+        })
+        .collect();
+
+    // Worn tiles: a checkerboard fading into grime where the noise is dark.
+    let tiles = Transform::new(
+        Checkerboard::new(Vector3::new(0.7, 0.7, 0.7), Vector3::new(0.2, 0.2, 0.2), 1.),
+        Similarity3::new(Vector3::zeros(), Vector3::y() * 45f64.to_radians(), 0.8),
+    );
+    let grime = Remap::new(
+        Fbm::new(Perlin::new(4), Vector3::zeros(), Vector3::repeat(1.), 1., 5),
+        0.3,
+        0.6,
+        0.,
+        1.,
+    );
+    let floor_color = Mix::new(Vector3::new(0.15, 0.12, 0.1), tiles, grime);
+
+    // A triplanar lava texture, which needs no texture coordinates on the cuboid.
+    let lava = ColorRamp::new(
+        Fbm::new(
+            Simplex::new(5),
+            Vector3::zeros(),
+            Vector3::repeat(1.),
+            0.4,
+            4,
+        ),
+        vec![
+            (0.3, Vector3::new(0.05, 0.02, 0.02)),
+            (0.5, Vector3::new(0.6, 0.1, 0.02)),
+            (0.7, Vector3::new(0.9, 0.7, 0.1)),
+        ],
+    );
+    let backdrop = ObjectDefinition {
+        shape: Box::new(Cuboid::new(12., 4., 0.5)),
+        material: Material::new_lambertian(Multiply::new(
+            Triplanar::new(lava, 1., 4.),
+            Vector3::repeat(0.9),
+        )),
+        y: 1.2,
+        z: -10.,
+        ..Default::default()
+    };
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(floor_color),
+        y: -0.8,
+        rx: (-90f64).to_radians(),
+        ..Default::default()
+    };
+
+    let light = ObjectDefinition {
+        shape: Box::new(Sphere::new(1.)),
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 4.),
+        y: 4.,
+        z: -4.,
+        ..Default::default()
+    };
+
+    objects.extend([backdrop, floor, light]);
+
+    let scene = Scene::new(camera, objects);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -0,0 +1,98 @@
+use std::{
+    env,
+    f64::consts::TAU,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use path_tracer::{
+    aperture::PinholeAperture, object::ObjectDefinition, renderer::PathTracer, shape::Plane,
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const MAX_PASSES: usize = 1000;
+const SAMPLES_PER_PASS: usize = 4;
+
+fn main() {
+    // The time budget in seconds can be given, pressing enter stops the render early.
+    let seconds: f64 = env::args().nth(1).map_or(30., |arg| {
+        arg.parse().expect("Time budget must be a number")
+    });
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6)),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let spheres = [
+        Material::new_lambertian(Vector3::new(0.8, 0.3, 0.2)),
+        Material::new_reflective(Vector3::new(1., 1., 1.), 0., 1., 1.5),
+        Material::new(Vector3::new(0.9, 0.9, 0.9), 0.1, false),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, material)| ObjectDefinition {
+        shape: Box::new(Sphere::new(0.8)),
+        material,
+        x: i as f64 * 2. - 2.,
+        y: -0.7,
+        z: -6.,
+        ..Default::default()
+    });
+
+    let light = ObjectDefinition {
+        shape: Box::new(Plane::new(2., 2.)),
+        material: Material::new_emissive(Vector3::new(1., 0.95, 0.9), 4.),
+        y: 2.5,
+        z: -6.,
+        rx: TAU / 4.,
+        ..Default::default()
+    };
+
+    let mut objects: Vec<_> = spheres.collect();
+    objects.extend([floor, light]);
+    let scene = Scene::new(camera, objects);
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let stop = cancel.clone();
+    thread::spawn(move || {
+        let mut line = String::new();
+        let _ = io::stdin().read_line(&mut line);
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    let renderer = PathTracer::new(10)
+        .tiled(SAMPLES_PER_PASS)
+        .progressive(MAX_PASSES)
+        .with_time_budget(Duration::from_secs_f64(seconds))
+        .with_cancel_flag(cancel);
+
+    let start = Instant::now();
+
+    let render_buffer = renderer.render_progressive(&scene, |preview, num_samples| {
+        println!("{num_samples} samples after {:?}", start.elapsed());
+        let image = preview.srgb().to_image_u8();
+        image.save("image.png").expect("Could not save image");
+    });
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -0,0 +1,74 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    object::ObjectDefinition,
+    renderer::PathTracer,
+    sampler::{
+        BlueNoiseSampler, HaltonSampler, IndependentSampler, SobolSampler, StratifiedSampler,
+    },
+    shape::Plane,
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 16;
+const SEED: u64 = 7;
+
+fn main() {
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 55., 1.0, 100.0, aperture, 5.);
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(20., 20.)),
+        material: Material::new_lambertian(Vector3::new(0.6, 0.6, 0.6)),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let sphere = ObjectDefinition {
+        shape: Box::new(Sphere::new(0.8)),
+        material: Material::new_lambertian(Vector3::new(0.8, 0.3, 0.2)),
+        y: -0.7,
+        z: -6.,
+        ..Default::default()
+    };
+
+    // A large light gives soft shadows, where the samplers differ the most.
+    let light = ObjectDefinition {
+        shape: Box::new(Plane::new(3., 3.)),
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 2.),
+        y: 2.5,
+        z: -6.,
+        rx: TAU / 4.,
+        ..Default::default()
+    };
+
+    let scene = Scene::new(camera, vec![floor, sphere, light]);
+
+    // The sampler can be given by name to compare the noise at a low sample count.
+    let name = env::args().nth(1).unwrap_or_else(|| "sobol".to_string());
+    let renderer = PathTracer::new(5).tiled(NUM_SAMPLES);
+    let renderer = match name.as_str() {
+        "independent" => renderer.with_sampler(IndependentSampler::new(NUM_SAMPLES, SEED)),
+        "stratified" => renderer.with_sampler(StratifiedSampler::new(4, 4, true, SEED)),
+        "halton" => renderer.with_sampler(HaltonSampler::new(NUM_SAMPLES, SEED)),
+        "sobol" => renderer.with_sampler(SobolSampler::new(NUM_SAMPLES, SEED)),
+        "blue-noise" => renderer.with_sampler(BlueNoiseSampler::new(NUM_SAMPLES, SEED)),
+        _ => panic!("Unknown sampler {name}"),
+    };
+
+    let start = Instant::now();
+
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
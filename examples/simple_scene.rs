@@ -35,9 +35,7 @@ fn main() {
 
     let light = ObjectDefinition {
         shape: Box::new(Sphere::new(1.)),
-        material: Material::Emissive {
-            color: Vector3::new(1., 1., 1.) * 1.,
-        },
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 1.),
         x: 1.0,
         y: -1.5,
         scale: 1.,
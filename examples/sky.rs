@@ -0,0 +1,62 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    light::PreethamSky,
+    object::ObjectDefinition,
+    renderer::PathTracer,
+    shape::{Cuboid, Plane},
+    Camera, Material, Renderer, Scene,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    // The sun elevation in degrees can be given to render other times of day.
+    let elevation: f64 = env::args()
+        .nth(1)
+        .map_or(30., |arg| arg.parse().expect("Elevation must be a number"));
+    let sky = PreethamSky::new(elevation.to_radians(), TAU / 10., 3.);
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(480, 240, 60., 1.0, 100.0, aperture, 5.);
+
+    let ground = ObjectDefinition {
+        shape: Box::new(Plane::new(200., 200.)),
+        material: Material::new_lambertian(Vector3::new(0.3, 0.3, 0.25)),
+        y: -1.5,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let wall = Material::new_lambertian(Vector3::new(0.8, 0.8, 0.75));
+    let buildings =
+        [(-3., 1., 2.), (0., 3., 1.5), (3., 2., 2.5)].map(|(x, z, height)| ObjectDefinition {
+            shape: Box::new(Cuboid::new(1.5, height, 1.5)),
+            material: wall.clone(),
+            x,
+            y: -1.5 + height / 2.,
+            z: -8. - z,
+            ..Default::default()
+        });
+
+    let mut objects = vec![ground];
+    objects.extend(buildings);
+
+    let scene = Scene::with_lights(camera, objects, sky.lights());
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -0,0 +1,97 @@
+use std::{env, f64::consts::TAU, time::Instant};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    object::ObjectDefinition,
+    renderer::PathTracer,
+    shader::{ImageTexture, WrapMode},
+    shape::{Cuboid, Cylinder, Plane},
+    Camera, Material, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const NUM_SAMPLES: usize = 100;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .expect("Usage: texture_example <path to .png or .exr file>");
+
+    let texture = ImageTexture::open(&path, WrapMode::Repeat).expect("Could not load texture");
+    let material = Material::new_lambertian(texture.clone());
+
+    // A glossy screen showing the texture, lighting the scene from behind.
+    let screen_material = Material::new_reflective(Vector3::new(0.05, 0.05, 0.05), 0.1, 0., 1.)
+        .with_emission(texture, 1.5);
+
+    let aperture = PinholeAperture;
+    let camera = Camera::new_at_origin(400, 300, 55., 1.0, 100.0, aperture, 5.);
+
+    let sphere = ObjectDefinition {
+        shape: Box::new(Sphere::new(0.8)),
+        material: material.clone(),
+        x: -1.8,
+        z: -6.,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let cuboid = ObjectDefinition {
+        shape: Box::new(Cuboid::new(1.2, 1.2, 1.2)),
+        material: material.clone(),
+        z: -6.,
+        rx: TAU / 16.,
+        ry: TAU / 10.,
+        ..Default::default()
+    };
+
+    let cylinder = ObjectDefinition {
+        shape: Box::new(Cylinder::new(0.6, 1.6)),
+        material: material.clone(),
+        x: 1.8,
+        z: -6.,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let floor = ObjectDefinition {
+        shape: Box::new(Plane::new(12., 12.)),
+        material,
+        y: -1.,
+        z: -6.,
+        rx: -TAU / 4.,
+        ..Default::default()
+    };
+
+    let screen = ObjectDefinition {
+        shape: Box::new(Plane::new(4., 2.5)),
+        material: screen_material,
+        y: 0.6,
+        z: -9.,
+        ..Default::default()
+    };
+
+    let light = ObjectDefinition {
+        shape: Box::new(Sphere::new(1.)),
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 3.),
+        y: 4.,
+        z: -3.,
+        ..Default::default()
+    };
+
+    let scene = Scene::new(camera, vec![sphere, cuboid, cylinder, floor, screen, light]);
+
+    let start = Instant::now();
+
+    let renderer = PathTracer::new(10).parallel(NUM_SAMPLES);
+    let render_buffer = renderer.render(&scene);
+
+    println!("Rendering took {:?}", start.elapsed());
+
+    let image = render_buffer.srgb().to_image_u8();
+
+    image.save("image.png").expect("Could not save image");
+}
@@ -1,16 +1,27 @@
+use std::f64::consts::TAU;
+
 use nalgebra::{Vector2, Vector3};
-use rand::{thread_rng, Rng};
-use rand_distr::Normal;
 
 use crate::Ray;
 
 pub trait Aperture: Send + Sync {
-    fn sample_offset(&self) -> Vector2<f64>;
+    /// Maps a point in [0, 1)² to an offset on the aperture.
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64>;
+
+    /// Whether every camera ray passes through the same point.
+    fn is_pinhole(&self) -> bool {
+        false
+    }
 
-    fn sample_ray(&self, Ray { origin, direction }: &Ray, focal_length: f64) -> Ray {
+    fn sample_ray(
+        &self,
+        Ray { origin, direction }: &Ray,
+        focal_length: f64,
+        u: Vector2<f64>,
+    ) -> Ray {
         let focal_point = direction * focal_length;
 
-        let offset = self.sample_offset();
+        let offset = self.sample_offset(u);
         let origin = origin + Vector3::new(offset.x, offset.y, 0.);
         let direction = (focal_point - origin.coords).normalize();
 
@@ -21,31 +32,34 @@ pub trait Aperture: Send + Sync {
 pub struct PinholeAperture;
 
 impl Aperture for PinholeAperture {
-    fn sample_offset(&self) -> Vector2<f64> {
+    fn sample_offset(&self, _u: Vector2<f64>) -> Vector2<f64> {
         todo!()
     }
-    fn sample_ray(&self, ray: &Ray, _focal_length: f64) -> Ray {
+    fn sample_ray(&self, ray: &Ray, _focal_length: f64, _u: Vector2<f64>) -> Ray {
         *ray
     }
+
+    fn is_pinhole(&self) -> bool {
+        true
+    }
 }
 
 pub struct GaussianAperture {
     pub std_dev: f64,
-    distribution: Normal<f64>,
 }
 
 impl GaussianAperture {
     pub fn new(std_dev: f64) -> Self {
-        Self {
-            std_dev,
-            distribution: Normal::new(0., std_dev).unwrap(),
-        }
+        Self { std_dev }
     }
 }
 
 impl Aperture for GaussianAperture {
-    fn sample_offset(&self) -> Vector2<f64> {
-        Vector2::from_distribution(&self.distribution, &mut thread_rng())
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64> {
+        // Box-Muller transform
+        let radius = (-2. * (1. - u.x).ln()).sqrt() * self.std_dev;
+        let angle = u.y * TAU;
+        Vector2::new(angle.cos(), angle.sin()) * radius
     }
 }
 
@@ -61,15 +75,16 @@ impl RegularPolygonAperture {
 }
 
 impl Aperture for RegularPolygonAperture {
-    fn sample_offset(&self) -> Vector2<f64> {
-        let mut rng = thread_rng();
-        let section: u8 = rng.gen_range(0..self.angles);
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64> {
+        // The first dimension picks the section and is reused for the position along it.
+        let scaled = u.x * self.angles as f64;
+        let section = scaled.floor().min(self.angles as f64 - 1.);
 
-        let angle_a = section as f64 * 2. * std::f64::consts::PI / self.angles as f64;
-        let angle_b = (section as f64 + 1.) * 2. * std::f64::consts::PI / self.angles as f64;
+        let angle_a = section * TAU / self.angles as f64;
+        let angle_b = (section + 1.) * TAU / self.angles as f64;
 
-        let weight = rng.gen::<f64>();
-        let distance: f64 = rng.gen::<f64>().sqrt() * self.radius;
+        let weight = scaled - section;
+        let distance: f64 = u.y.sqrt() * self.radius;
 
         let vector_a = Vector2::new(angle_a.cos(), angle_a.sin()) * distance;
         let vector_b = Vector2::new(angle_b.cos(), angle_b.sin()) * distance;
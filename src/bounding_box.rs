@@ -0,0 +1,124 @@
+use nalgebra::{Point3, Similarity3, Vector3};
+
+use crate::Ray;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point3<f64>, max: Point3<f64>) -> Self {
+        Self { min, max }
+    }
+
+    /// A bounding box that contains nothing, useful as the starting point of a union.
+    pub fn empty() -> Self {
+        Self {
+            min: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points<'a, I: IntoIterator<Item = &'a Point3<f64>>>(points: I) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |bounds, point| bounds.grow(point))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn grow(&self, point: &Point3<f64>) -> Self {
+        Self {
+            min: self.min.inf(point),
+            max: self.max.sup(point),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn size(&self) -> Vector3<f64> {
+        self.max - self.min
+    }
+
+    pub fn centroid(&self) -> Point3<f64> {
+        self.min + self.size() / 2.
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            0.
+        } else {
+            let size = self.size();
+            2. * (size.x * size.y + size.y * size.z + size.x * size.z)
+        }
+    }
+
+    pub fn corners(&self) -> [Point3<f64>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    pub fn transform_similarity(&self, transform: &Similarity3<f64>) -> Self {
+        if self.is_empty() {
+            *self
+        } else {
+            let corners = self
+                .corners()
+                .map(|corner| transform.transform_point(&corner));
+            Self::from_points(&corners)
+        }
+    }
+
+    /// The squared distance from `point` to the closest point of the box, 0 inside of it.
+    pub fn distance_squared(&self, point: &Point3<f64>) -> f64 {
+        (self.min - point)
+            .sup(&(point - self.max))
+            .sup(&Vector3::zeros())
+            .magnitude_squared()
+    }
+
+    /// Returns the distance at which the ray enters the box, or 0 if it starts inside of it.
+    /// `inverse_direction` is the component-wise inverse of the ray direction.
+    pub fn intersection_distance(
+        &self,
+        ray: &Ray,
+        inverse_direction: &Vector3<f64>,
+        max_distance: f64,
+    ) -> Option<f64> {
+        let mut near: f64 = 0.;
+        let mut far = max_distance;
+
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+
+            // f64::min and f64::max ignore NaN, which happens for flat boxes and parallel rays.
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+
+        if near <= far {
+            Some(near)
+        } else {
+            None
+        }
+    }
+}
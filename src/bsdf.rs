@@ -0,0 +1,114 @@
+use std::f64::consts::TAU;
+
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+use crate::{orthonormal_basis, ShadingPoint};
+
+mod conductor;
+mod dielectric;
+mod glossy;
+mod lambertian;
+pub mod microfacet;
+
+pub use conductor::Conductor;
+pub use dielectric::{fresnel_dielectric, refract, Dielectric};
+pub use glossy::Glossy;
+pub use lambertian::Lambertian;
+
+/// A sampled incident direction, in the local shading frame.
+#[derive(Clone, Copy, Debug)]
+pub struct BsdfSample {
+    pub wi: Vector3<f64>,
+    /// The BSDF value for `wi`. For specular samples this is the value that makes
+    /// `f * |cos(wi)| / pdf` the throughput of the sample.
+    pub f: Vector3<f64>,
+    /// Solid angle density of `wi`, or the probability of the chosen lobe when `specular` is set.
+    pub pdf: f64,
+    /// Set when `wi` was picked by a delta distribution, such directions are never returned by
+    /// [`Bsdf::eval`] or [`Bsdf::pdf`].
+    pub specular: bool,
+    /// The relative index of refraction crossed by a transmitted sample, 1 for reflections.
+    pub eta: f64,
+}
+
+/// Describes how light scatters at a surface. All directions are given in the local shading
+/// frame, where the surface normal is the positive z axis, and point away from the surface:
+/// `wo` towards the viewer and `wi` towards the light.
+pub trait Bsdf: Send + Sync {
+    /// Samples an incident direction for the outgoing direction `wo`. The uniformly distributed
+    /// number `uc` picks between lobes, `u` picks a direction within the lobe.
+    fn sample(
+        &self,
+        wo: &Vector3<f64>,
+        point: &ShadingPoint,
+        uc: f64,
+        u: Vector2<f64>,
+    ) -> Option<BsdfSample>;
+
+    /// The BSDF value for light arriving from `wi` and leaving towards `wo`, without the cosine
+    /// term.
+    fn eval(&self, wo: &Vector3<f64>, wi: &Vector3<f64>, point: &ShadingPoint) -> Vector3<f64>;
+
+    /// Solid angle density with which [`Bsdf::sample`] picks `wi` for `wo`.
+    fn pdf(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64;
+
+    /// Whether all scattering is perfectly specular, in which case [`Bsdf::eval`] is always zero
+    /// and sampling lights is pointless.
+    fn is_specular(&self) -> bool {
+        false
+    }
+}
+
+/// Samples a direction in the upper hemisphere with a density proportional to its cosine with
+/// the z axis, the density is `z / PI`.
+pub fn cosine_sample_hemisphere(u: Vector2<f64>) -> Vector3<f64> {
+    // Malley's method: project a uniformly sampled point on the unit disk up to the hemisphere.
+    let radius = u.x.sqrt();
+    let angle = u.y * TAU;
+    let x = radius * angle.cos();
+    let y = radius * angle.sin();
+    Vector3::new(x, y, (1. - x * x - y * y).max(0.).sqrt())
+}
+
+/// Mirrors a direction through the tangent plane when `flip` is set.
+pub(crate) fn flip_z(direction: &Vector3<f64>, flip: bool) -> Vector3<f64> {
+    if flip {
+        Vector3::new(direction.x, direction.y, -direction.z)
+    } else {
+        *direction
+    }
+}
+
+/// Orthonormal frame around a shading normal, used to move directions in and out of the local
+/// frame of a [`Bsdf`].
+#[derive(Clone, Copy, Debug)]
+pub struct ShadingFrame {
+    pub tangent: Vector3<f64>,
+    pub bitangent: Vector3<f64>,
+    pub normal: Vector3<f64>,
+}
+
+impl ShadingFrame {
+    pub fn new(normal: &Vector3<f64>) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        Self {
+            tangent,
+            bitangent,
+            normal: *normal,
+        }
+    }
+
+    pub fn to_local(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(
+            direction.dot(&self.tangent),
+            direction.dot(&self.bitangent),
+            direction.dot(&self.normal),
+        )
+    }
+
+    pub fn to_world(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        self.tangent * direction.x + self.bitangent * direction.y + self.normal * direction.z
+    }
+}
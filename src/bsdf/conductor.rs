@@ -0,0 +1,160 @@
+use nalgebra as na;
+
+use na::{Complex, Vector2, Vector3};
+
+use crate::{
+    bsdf::{flip_z, microfacet::TrowbridgeReitz, Bsdf, BsdfSample},
+    ShadingPoint,
+};
+
+/// Fresnel reflectance of a conductor with complex index of refraction `eta + i k`.
+fn fresnel_complex(cos_theta_i: f64, eta: Complex<f64>) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0., 1.);
+    let sin2_theta_i = 1. - cos_theta_i * cos_theta_i;
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    let cos_theta_t = (1. - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel.norm_sqr() + r_perpendicular.norm_sqr()) / 2.
+}
+
+/// A metal described by its complex index of refraction per RGB channel, with GGX microfacet
+/// roughness. It reflects the same from both sides of a surface.
+#[derive(Debug, Clone, Copy)]
+pub struct Conductor {
+    eta: Vector3<f64>,
+    k: Vector3<f64>,
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Vector3<f64>, k: Vector3<f64>, roughness: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Vector3::new(0.143119, 0.374957, 1.44248),
+            Vector3::new(3.98316, 2.38572, 1.60322),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Vector3::new(0.200438, 0.924033, 1.10221),
+            Vector3::new(3.91295, 2.45285, 2.14219),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Vector3::new(0.155265, 0.116723, 0.138342),
+            Vector3::new(4.82835, 3.12225, 2.14696),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Vector3::new(1.65746, 0.880369, 0.521229),
+            Vector3::new(9.22387, 6.26952, 4.837),
+            roughness,
+        )
+    }
+
+    fn fresnel(&self, cos_theta: f64) -> Vector3<f64> {
+        Vector3::from_fn(|i, _| fresnel_complex(cos_theta, Complex::new(self.eta[i], self.k[i])))
+    }
+}
+
+impl Bsdf for Conductor {
+    fn sample(
+        &self,
+        wo: &Vector3<f64>,
+        point: &ShadingPoint,
+        _uc: f64,
+        u: Vector2<f64>,
+    ) -> Option<BsdfSample> {
+        if wo.z == 0. {
+            return None;
+        }
+        let flip = wo.z < 0.;
+        let wo = flip_z(wo, flip);
+
+        if self.distribution.is_smooth() {
+            let wi = Vector3::new(-wo.x, -wo.y, wo.z);
+            return Some(BsdfSample {
+                wi: flip_z(&wi, flip),
+                f: self.fresnel(wi.z) / wi.z,
+                pdf: 1.,
+                specular: true,
+                eta: 1.,
+            });
+        }
+
+        let wm = self.distribution.sample_wm(&wo, u);
+        let wi = 2. * wo.dot(&wm) * wm - wo;
+        if wi.z <= 0. {
+            return None;
+        }
+
+        let wo = flip_z(&wo, flip);
+        let wi = flip_z(&wi, flip);
+        let pdf = self.pdf(&wo, &wi);
+        (pdf > 0.).then(|| BsdfSample {
+            wi,
+            f: self.eval(&wo, &wi, point),
+            pdf,
+            specular: false,
+            eta: 1.,
+        })
+    }
+
+    fn eval(&self, wo: &Vector3<f64>, wi: &Vector3<f64>, _point: &ShadingPoint) -> Vector3<f64> {
+        if self.distribution.is_smooth() || wo.z * wi.z <= 0. {
+            return Vector3::zeros();
+        }
+
+        let flip = wo.z < 0.;
+        let wo = flip_z(wo, flip);
+        let wi = flip_z(wi, flip);
+
+        let wm = wo + wi;
+        if wm.magnitude_squared() == 0. {
+            return Vector3::zeros();
+        }
+        let wm = wm.normalize();
+
+        self.fresnel(wo.dot(&wm).abs()) * self.distribution.d(&wm) * self.distribution.g(&wo, &wi)
+            / (4. * wo.z * wi.z)
+    }
+
+    fn pdf(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        if self.distribution.is_smooth() || wo.z * wi.z <= 0. {
+            return 0.;
+        }
+
+        let flip = wo.z < 0.;
+        let wo = flip_z(wo, flip);
+        let wi = flip_z(wi, flip);
+
+        let wm = wo + wi;
+        if wm.magnitude_squared() == 0. {
+            return 0.;
+        }
+        let wm = wm.normalize();
+
+        self.distribution.visible_d(&wo, &wm) / (4. * wo.dot(&wm).abs())
+    }
+
+    fn is_specular(&self) -> bool {
+        self.distribution.is_smooth()
+    }
+}
@@ -0,0 +1,212 @@
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+use crate::{
+    bsdf::{microfacet::TrowbridgeReitz, Bsdf, BsdfSample},
+    ShadingPoint,
+};
+
+/// Fresnel reflectance of an interface between two dielectrics, where `eta` is the index of
+/// refraction on the side opposite to the normal relative to the side of the normal.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(-1., 1.);
+    let (cos_theta_i, eta) = if cos_theta_i < 0. {
+        (-cos_theta_i, 1. / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+
+    let sin2_theta_t = (1. - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1. {
+        // Total internal reflection
+        return 1.;
+    }
+    let cos_theta_t = (1. - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.
+}
+
+/// Refracts `wi` through a surface with normal `normal` following Snell's law. Returns the
+/// refracted direction and the relative index of refraction along the way, or `None` on total
+/// internal reflection.
+pub fn refract(wi: &Vector3<f64>, normal: &Vector3<f64>, eta: f64) -> Option<(Vector3<f64>, f64)> {
+    let mut cos_theta_i = normal.dot(wi);
+    let (normal, eta) = if cos_theta_i < 0. {
+        cos_theta_i = -cos_theta_i;
+        (-normal, 1. / eta)
+    } else {
+        (*normal, eta)
+    };
+
+    let sin2_theta_t = (1. - cos_theta_i * cos_theta_i).max(0.) / (eta * eta);
+    if sin2_theta_t >= 1. {
+        return None;
+    }
+    let cos_theta_t = (1. - sin2_theta_t).sqrt();
+
+    Some((-wi / eta + (cos_theta_i / eta - cos_theta_t) * normal, eta))
+}
+
+/// A transparent interface such as glass or water, smooth or with GGX microfacet roughness.
+/// Light is reflected or refracted with the probability given by the Fresnel equations.
+/// `ior` is the index of refraction on the inside, the side opposite to the normal, relative to
+/// the outside.
+#[derive(Debug, Clone, Copy)]
+pub struct Dielectric {
+    ior: f64,
+    distribution: TrowbridgeReitz,
+}
+
+impl Dielectric {
+    pub fn new(ior: f64, roughness: f64) -> Self {
+        Self {
+            ior,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+
+    /// The generalized half vector of `wo` and `wi` facing the normal, along with the relative
+    /// index of refraction, or `None` when the configuration is impossible.
+    fn half_vector(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> Option<(Vector3<f64>, f64)> {
+        let cos_theta_o = wo.z;
+        let cos_theta_i = wi.z;
+        if cos_theta_o == 0. || cos_theta_i == 0. {
+            return None;
+        }
+
+        let eta = if cos_theta_o * cos_theta_i > 0. {
+            1.
+        } else if cos_theta_o > 0. {
+            self.ior
+        } else {
+            1. / self.ior
+        };
+
+        let wm = wi * eta + wo;
+        if wm.magnitude_squared() == 0. {
+            return None;
+        }
+        let mut wm = wm.normalize();
+        if wm.z < 0. {
+            wm = -wm;
+        }
+
+        // Microfacets seen from behind don't contribute.
+        if wm.dot(wi) * cos_theta_i < 0. || wm.dot(wo) * cos_theta_o < 0. {
+            return None;
+        }
+
+        Some((wm, eta))
+    }
+}
+
+impl Bsdf for Dielectric {
+    fn sample(
+        &self,
+        wo: &Vector3<f64>,
+        point: &ShadingPoint,
+        uc: f64,
+        u: Vector2<f64>,
+    ) -> Option<BsdfSample> {
+        if self.ior == 1. || self.distribution.is_smooth() {
+            let reflectance = fresnel_dielectric(wo.z, self.ior);
+            let transmittance = 1. - reflectance;
+
+            return if uc < reflectance {
+                let wi = Vector3::new(-wo.x, -wo.y, wo.z);
+                Some(BsdfSample {
+                    wi,
+                    f: Vector3::repeat(reflectance / wi.z.abs()),
+                    pdf: reflectance,
+                    specular: true,
+                    eta: 1.,
+                })
+            } else {
+                let (wi, eta) = refract(wo, &Vector3::z(), self.ior)?;
+                // Radiance is compressed into a smaller solid angle when entering a denser
+                // medium.
+                Some(BsdfSample {
+                    wi,
+                    f: Vector3::repeat(transmittance / wi.z.abs() / (eta * eta)),
+                    pdf: transmittance,
+                    specular: true,
+                    eta,
+                })
+            };
+        }
+
+        let wm = self.distribution.sample_wm(wo, u);
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.ior);
+
+        let (wi, eta) = if uc < reflectance {
+            let wi = 2. * wo.dot(&wm) * wm - wo;
+            if wo.z * wi.z <= 0. {
+                return None;
+            }
+            (wi, 1.)
+        } else {
+            let (wi, eta) = refract(wo, &wm, self.ior)?;
+            if wo.z * wi.z >= 0. {
+                return None;
+            }
+            (wi, eta)
+        };
+
+        let pdf = self.pdf(wo, &wi);
+        (pdf > 0.).then(|| BsdfSample {
+            wi,
+            f: self.eval(wo, &wi, point),
+            pdf,
+            specular: false,
+            eta,
+        })
+    }
+
+    fn eval(&self, wo: &Vector3<f64>, wi: &Vector3<f64>, _point: &ShadingPoint) -> Vector3<f64> {
+        if self.ior == 1. || self.distribution.is_smooth() {
+            return Vector3::zeros();
+        }
+        let Some((wm, eta)) = self.half_vector(wo, wi) else {
+            return Vector3::zeros();
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.ior);
+        let d = self.distribution.d(&wm);
+        let g = self.distribution.g(wo, wi);
+
+        let f = if wo.z * wi.z > 0. {
+            d * g * reflectance / (4. * wi.z * wo.z).abs()
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2) * wi.z * wo.z;
+            d * g * (1. - reflectance) * (wi.dot(&wm) * wo.dot(&wm) / denominator).abs()
+                / (eta * eta)
+        };
+        Vector3::repeat(f)
+    }
+
+    fn pdf(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        if self.ior == 1. || self.distribution.is_smooth() {
+            return 0.;
+        }
+        let Some((wm, eta)) = self.half_vector(wo, wi) else {
+            return 0.;
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.ior);
+        let visible_d = self.distribution.visible_d(wo, &wm);
+
+        if wo.z * wi.z > 0. {
+            visible_d / (4. * wo.dot(&wm).abs()) * reflectance
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+            visible_d * wi.dot(&wm).abs() / denominator * (1. - reflectance)
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        self.ior == 1. || self.distribution.is_smooth()
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    f64::consts::{FRAC_PI_2, PI, TAU},
+    sync::Arc,
+};
+
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+use crate::{
+    bsdf::{flip_z, Bsdf, BsdfSample},
+    find_normal,
+    function_approximation::ProbabilityDensityFunction,
+    reflect, Shader, ShadingPoint,
+};
+
+// fn ggx(x: f64, roughness: f64) -> f64 {
+//     let roughness = roughness.powf(2.) + 0.0001;
+//     let x = 1. - x;
+//     E.powf(-(x * x) / (roughness * roughness) / 2.) / (roughness * TAU.sqrt())
+// }
+
+fn ggx(x: f64, roughness: f64) -> f64 {
+    if roughness < 0.001 {
+        if x >= 0.9999 {
+            1.
+        } else {
+            0.
+        }
+    } else {
+        roughness.powi(2) / (PI * (x.powi(2) * (roughness.powi(2) - 1.) + 1.).powi(2))
+    }
+}
+
+/// The original material model of this renderer: a reflection around a scatter normal whose
+/// angle with the surface normal follows a tabulated distribution, mixed with a transmission
+/// that bends the ray by interpolating towards the scatter normal. It scatters the same from
+/// both sides of a surface, transmission is treated as specular.
+///
+/// The transmission is a legacy approximation that follows neither Snell's law nor the Fresnel
+/// equations, it is only kept to render old scenes the same. Transparent materials should use
+/// [`Dielectric`](super::Dielectric), which [`Material::new_reflective`](crate::Material::new_reflective)
+/// builds whenever it is asked for transmission.
+#[derive(Clone)]
+pub struct Glossy {
+    color: Arc<dyn Shader>,
+    roughness: f64,
+    transmission: f64,
+    ior: f64,
+    pdf: ProbabilityDensityFunction,
+}
+
+impl Glossy {
+    pub fn new<S: Shader + 'static>(color: S, roughness: f64, transmission: f64, ior: f64) -> Self {
+        let pdf = ProbabilityDensityFunction::build(|x| ggx(x, roughness), 1000);
+        Self {
+            color: Arc::new(color),
+            roughness,
+            transmission,
+            ior,
+            pdf,
+        }
+    }
+
+    /// Solid angle density of reflecting `wo` into `wi` through a sampled scatter normal,
+    /// without folding directions below the surface. Both directions are in the upper
+    /// hemisphere.
+    fn reflection_density(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        let half_vector = find_normal(&-wo, wi);
+
+        // The scatter normal makes an angle of (1 - x) * 90 degrees with the normal, where x is
+        // drawn from `pdf`, and has a uniformly distributed azimuth.
+        let angle = half_vector.z.abs().clamp(-1., 1.).acos();
+        let x = 1. - angle / FRAC_PI_2;
+        let half_vector_density =
+            self.pdf.pdf.apply(x).unwrap_or(0.) / (PI * PI * angle.sin().max(1e-6));
+
+        let wo_dot = wo.dot(&half_vector).abs();
+        if wo_dot > 0. {
+            half_vector_density / (4. * wo_dot)
+        } else {
+            0.
+        }
+    }
+}
+
+impl Bsdf for Glossy {
+    fn sample(
+        &self,
+        wo: &Vector3<f64>,
+        point: &ShadingPoint,
+        uc: f64,
+        u: Vector2<f64>,
+    ) -> Option<BsdfSample> {
+        let flip = wo.z < 0.;
+        let incoming = -flip_z(wo, flip);
+
+        let transmitted = uc < self.transmission;
+
+        let scatter_normal = if self.is_specular() {
+            Vector3::z()
+        } else {
+            let angle = (1. - self.pdf.sample_at(u.x)) * FRAC_PI_2;
+            let azimuth = u.y * TAU;
+            Vector3::new(
+                azimuth.cos() * angle.sin(),
+                azimuth.sin() * angle.sin(),
+                angle.cos(),
+            )
+        };
+
+        let color = self.color.shade(point);
+
+        if transmitted {
+            let wi = if scatter_normal.dot(&incoming) >= 0. {
+                scatter_normal.slerp(&incoming, self.ior)
+            } else {
+                (-scatter_normal).slerp(&incoming, 1. / self.ior)
+            };
+
+            let cosine = wi.z.abs();
+            return (cosine > 0.).then(|| BsdfSample {
+                wi: flip_z(&wi, flip),
+                f: color * self.transmission / cosine,
+                pdf: self.transmission,
+                specular: true,
+                eta: 1.,
+            });
+        }
+
+        let mut wi = reflect(&incoming, &scatter_normal);
+        if wi.z < 0. {
+            wi.z = -wi.z;
+        }
+        let wi = flip_z(&wi, flip);
+
+        if self.is_specular() {
+            let cosine = wi.z.abs();
+            (cosine > 0.).then(|| BsdfSample {
+                wi,
+                f: color * (1. - self.transmission) / cosine,
+                pdf: 1. - self.transmission,
+                specular: true,
+                eta: 1.,
+            })
+        } else {
+            let pdf = self.pdf(wo, &wi);
+            (pdf > 0. && wi.z != 0.).then(|| BsdfSample {
+                wi,
+                f: color * pdf / wi.z.abs(),
+                pdf,
+                specular: false,
+                eta: 1.,
+            })
+        }
+    }
+
+    /// Defined such that importance sampling it yields exactly the color as weight.
+    fn eval(&self, wo: &Vector3<f64>, wi: &Vector3<f64>, point: &ShadingPoint) -> Vector3<f64> {
+        let pdf = self.pdf(wo, wi);
+        let cosine = wi.z.abs();
+        if pdf > 0. && cosine > 0. {
+            self.color.shade(point) * pdf / cosine
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    fn pdf(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        if self.is_specular() || wo.z * wi.z <= 0. {
+            return 0.;
+        }
+
+        let flip = wo.z < 0.;
+        let wo = flip_z(wo, flip);
+        let wi = flip_z(wi, flip);
+
+        // Directions that would end up below the surface are mirrored back up.
+        let mirrored = Vector3::new(wi.x, wi.y, -wi.z);
+        (1. - self.transmission)
+            * (self.reflection_density(&wo, &wi) + self.reflection_density(&wo, &mirrored))
+    }
+
+    fn is_specular(&self) -> bool {
+        self.roughness < 0.001
+    }
+}
@@ -0,0 +1,65 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+use crate::{
+    bsdf::{cosine_sample_hemisphere, Bsdf, BsdfSample},
+    Shader, ShadingPoint,
+};
+
+/// Ideal diffuse reflection, scattering light equally in all directions on the side it arrived
+/// from.
+#[derive(Clone)]
+pub struct Lambertian {
+    albedo: Arc<dyn Shader>,
+}
+
+impl Lambertian {
+    pub fn new<S: Shader + 'static>(albedo: S) -> Self {
+        Self {
+            albedo: Arc::new(albedo),
+        }
+    }
+}
+
+impl Bsdf for Lambertian {
+    fn sample(
+        &self,
+        wo: &Vector3<f64>,
+        point: &ShadingPoint,
+        _uc: f64,
+        u: Vector2<f64>,
+    ) -> Option<BsdfSample> {
+        let mut wi = cosine_sample_hemisphere(u);
+        if wo.z < 0. {
+            wi.z = -wi.z;
+        }
+
+        let pdf = self.pdf(wo, &wi);
+        (pdf > 0.).then(|| BsdfSample {
+            wi,
+            f: self.eval(wo, &wi, point),
+            pdf,
+            specular: false,
+            eta: 1.,
+        })
+    }
+
+    fn eval(&self, wo: &Vector3<f64>, wi: &Vector3<f64>, point: &ShadingPoint) -> Vector3<f64> {
+        if wo.z * wi.z > 0. {
+            self.albedo.shade(point) / PI
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    fn pdf(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        if wo.z * wi.z > 0. {
+            wi.z.abs() / PI
+        } else {
+            0.
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::f64::consts::{PI, TAU};
+
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+/// The isotropic GGX, or Trowbridge-Reitz, distribution of microfacet normals with Smith
+/// masking-shadowing. Directions are in the local shading frame.
+#[derive(Debug, Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.max(1e-4),
+        }
+    }
+
+    /// Maps a perceptually linear roughness in [0, 1] to the alpha parameter.
+    pub fn from_roughness(roughness: f64) -> Self {
+        Self::new(roughness * roughness)
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Whether the surface is smooth enough to be treated as a perfect mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < 1e-3
+    }
+
+    /// Density of microfacet normals `wm` per unit of projected area.
+    pub fn d(&self, wm: &Vector3<f64>) -> f64 {
+        let cos2_theta = wm.z * wm.z;
+        if cos2_theta <= 0. {
+            return 0.;
+        }
+
+        let alpha2 = self.alpha * self.alpha;
+        let tan2_theta = (1. - cos2_theta) / cos2_theta;
+        let e = 1. + tan2_theta / alpha2;
+        1. / (PI * alpha2 * cos2_theta * cos2_theta * e * e)
+    }
+
+    fn lambda(&self, w: &Vector3<f64>) -> f64 {
+        let cos2_theta = w.z * w.z;
+        if cos2_theta <= 0. {
+            return f64::INFINITY;
+        }
+
+        let tan2_theta = (1. - cos2_theta) / cos2_theta;
+        ((1. + self.alpha * self.alpha * tan2_theta).sqrt() - 1.) / 2.
+    }
+
+    /// Fraction of microfacets visible from direction `w`.
+    pub fn g1(&self, w: &Vector3<f64>) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    /// Height-correlated fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the normals visible from `w`, which is what [`TrowbridgeReitz::sample_wm`]
+    /// samples from.
+    pub fn visible_d(&self, w: &Vector3<f64>, wm: &Vector3<f64>) -> f64 {
+        if w.z == 0. {
+            return 0.;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Samples a microfacet normal visible from `w`, following Heitz, "Sampling the GGX
+    /// Distribution of Visible Normals".
+    pub fn sample_wm(&self, w: &Vector3<f64>, u: Vector2<f64>) -> Vector3<f64> {
+        // Stretch the view direction to the hemisphere configuration.
+        let mut wh = Vector3::new(self.alpha * w.x, self.alpha * w.y, w.z).normalize();
+        if wh.z < 0. {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z < 0.99999 {
+            Vector3::z().cross(&wh).normalize()
+        } else {
+            Vector3::x()
+        };
+        let t2 = wh.cross(&t1);
+
+        // Sample a point on the projected disk, warped towards the visible half.
+        let radius = u.x.sqrt();
+        let angle = TAU * u.y;
+        let x = radius * angle.cos();
+        let mut y = radius * angle.sin();
+        let h = (1. - x * x).sqrt();
+        let s = (1. + wh.z) / 2.;
+        y = (1. - s) * h + s * y;
+
+        let z = (1. - x * x - y * y).max(0.).sqrt();
+        let nh = t1 * x + t2 * y + wh * z;
+
+        Vector3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
@@ -0,0 +1,310 @@
+use nalgebra::{Point3, Vector3};
+
+use crate::{BoundingBox, Ray};
+
+const NUM_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f64 = 1.;
+const INTERSECTION_COST: f64 = 1.;
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bounds: BoundingBox,
+    /// For leaves the index of the first primitive, for interior nodes the index of the second
+    /// child. The first child of an interior node is always stored right after it.
+    offset: usize,
+    /// Number of primitives in a leaf, 0 for interior nodes.
+    count: usize,
+    axis: usize,
+}
+
+/// A bounding volume hierarchy over primitives identified by their index, built using the
+/// surface area heuristic.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitives: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BuildPrimitive {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: Vector3<f64>,
+}
+
+impl Bvh {
+    /// Builds a hierarchy over the given bounds, primitives with empty bounds are left out as
+    /// they can never be hit.
+    pub fn build(bounds: &[BoundingBox]) -> Self {
+        let mut primitives: Vec<BuildPrimitive> = bounds
+            .iter()
+            .enumerate()
+            .filter(|(_, bounds)| !bounds.is_empty())
+            .map(|(index, bounds)| BuildPrimitive {
+                index,
+                bounds: *bounds,
+                centroid: bounds.centroid().coords,
+            })
+            .collect();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * primitives.len()),
+            primitives: Vec::with_capacity(primitives.len()),
+        };
+
+        if !primitives.is_empty() {
+            bvh.build_node(&mut primitives);
+        }
+
+        bvh
+    }
+
+    fn build_node(&mut self, primitives: &mut [BuildPrimitive]) -> usize {
+        let bounds = primitives
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, primitive| {
+                bounds.union(&primitive.bounds)
+            });
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds,
+            offset: self.primitives.len(),
+            count: primitives.len(),
+            axis: 0,
+        });
+
+        if primitives.len() <= 1 {
+            self.primitives.push(primitives[0].index);
+            return node_index;
+        }
+
+        match Self::find_split(primitives, &bounds) {
+            Some((axis, split)) => {
+                let mut left = 0;
+                for i in 0..primitives.len() {
+                    if primitives[i].centroid[axis] < split {
+                        primitives.swap(i, left);
+                        left += 1;
+                    }
+                }
+                // Fall back to a median split when all centroids end up on one side.
+                if left == 0 || left == primitives.len() {
+                    primitives.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+                    left = primitives.len() / 2;
+                }
+
+                let (left_primitives, right_primitives) = primitives.split_at_mut(left);
+                self.build_node(left_primitives);
+                let right_index = self.build_node(right_primitives);
+
+                let node = &mut self.nodes[node_index];
+                node.offset = right_index;
+                node.count = 0;
+                node.axis = axis;
+            }
+            None => {
+                self.primitives
+                    .extend(primitives.iter().map(|primitive| primitive.index));
+            }
+        }
+
+        node_index
+    }
+
+    /// Finds the best split along any axis using binned SAH, or `None` if making a leaf is
+    /// cheaper.
+    fn find_split(primitives: &[BuildPrimitive], bounds: &BoundingBox) -> Option<(usize, f64)> {
+        let centroid_bounds = primitives
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, primitive| {
+                bounds.grow(&primitive.centroid.into())
+            });
+        let centroid_size = centroid_bounds.size();
+
+        let mut best: Option<(usize, f64)> = None;
+        let mut best_cost = f64::INFINITY;
+
+        for axis in 0..3 {
+            if centroid_size[axis] <= 0. {
+                continue;
+            }
+
+            let mut bin_bounds = [BoundingBox::empty(); NUM_BINS];
+            let mut bin_counts = [0usize; NUM_BINS];
+            let bin_index = |centroid: f64| {
+                let relative = (centroid - centroid_bounds.min[axis]) / centroid_size[axis];
+                ((relative * NUM_BINS as f64) as usize).min(NUM_BINS - 1)
+            };
+
+            for primitive in primitives {
+                let bin = bin_index(primitive.centroid[axis]);
+                bin_counts[bin] += 1;
+                bin_bounds[bin] = bin_bounds[bin].union(&primitive.bounds);
+            }
+
+            for split in 1..NUM_BINS {
+                let (left_bounds, left_count) = bin_bounds[..split]
+                    .iter()
+                    .zip(&bin_counts[..split])
+                    .fold((BoundingBox::empty(), 0), |(bounds, count), (b, c)| {
+                        (bounds.union(b), count + c)
+                    });
+                let (right_bounds, right_count) = bin_bounds[split..]
+                    .iter()
+                    .zip(&bin_counts[split..])
+                    .fold((BoundingBox::empty(), 0), |(bounds, count), (b, c)| {
+                        (bounds.union(b), count + c)
+                    });
+
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+
+                let cost = left_bounds.surface_area() * left_count as f64
+                    + right_bounds.surface_area() * right_count as f64;
+                if cost < best_cost {
+                    best_cost = cost;
+                    let split_position = centroid_bounds.min[axis]
+                        + centroid_size[axis] * split as f64 / NUM_BINS as f64;
+                    best = Some((axis, split_position));
+                }
+            }
+        }
+
+        let split_cost = TRAVERSAL_COST + INTERSECTION_COST * best_cost / bounds.surface_area();
+        let leaf_cost = INTERSECTION_COST * primitives.len() as f64;
+
+        if primitives.len() > MAX_LEAF_SIZE || split_cost < leaf_cost {
+            best.or_else(|| {
+                // All centroids coincide, split along the longest axis of the bounds instead.
+                let size = bounds.size();
+                let axis = size.imax();
+                Some((axis, bounds.centroid()[axis]))
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Finds the closest hit along the ray. `intersect_primitive` receives a primitive index and
+    /// the distance of the closest hit so far, and returns the distance of a closer hit, if any.
+    pub fn intersect<F: FnMut(usize, f64) -> Option<f64>>(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        mut intersect_primitive: F,
+    ) {
+        let mut max_distance = max_distance;
+        self.traverse(ray, &mut max_distance, |index, max_distance| {
+            if let Some(distance) = intersect_primitive(index, *max_distance) {
+                if distance < *max_distance {
+                    *max_distance = distance;
+                }
+            }
+            false
+        });
+    }
+
+    /// Returns whether any primitive is hit closer than `max_distance`, stopping at the first one
+    /// found. `blocks_primitive` receives a primitive index and the maximum distance.
+    pub fn any_hit<F: FnMut(usize, f64) -> bool>(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        mut blocks_primitive: F,
+    ) -> bool {
+        let mut max_distance = max_distance;
+        self.traverse(ray, &mut max_distance, |index, max_distance| {
+            blocks_primitive(index, *max_distance)
+        })
+    }
+
+    /// Finds the primitive closest to `point`. `distance_primitive` receives a primitive index and
+    /// the squared distance of the closest primitive so far, and returns the squared distance of
+    /// a closer one, if any.
+    pub fn closest<F: FnMut(usize, f64) -> Option<f64>>(
+        &self,
+        point: &Point3<f64>,
+        mut distance_primitive: F,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut max_distance = f64::INFINITY;
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(node_index) = stack.pop() {
+            let node: &BvhNode = &self.nodes[node_index];
+            if node.bounds.distance_squared(point) > max_distance {
+                continue;
+            }
+
+            if node.count > 0 {
+                for &primitive in &self.primitives[node.offset..node.offset + node.count] {
+                    if let Some(distance) = distance_primitive(primitive, max_distance) {
+                        max_distance = max_distance.min(distance);
+                    }
+                }
+            } else {
+                // Visit the nearer child first so the farther one is more likely to be skipped.
+                let near = self.nodes[node_index + 1].bounds.distance_squared(point);
+                let far = self.nodes[node.offset].bounds.distance_squared(point);
+                if near <= far {
+                    stack.push(node.offset);
+                    stack.push(node_index + 1);
+                } else {
+                    stack.push(node_index + 1);
+                    stack.push(node.offset);
+                }
+            }
+        }
+    }
+
+    /// Visits the leaves hit by the ray front to back, `visit` can shrink the maximum distance
+    /// and stops the traversal by returning true. Returns whether the traversal was stopped.
+    fn traverse<F: FnMut(usize, &mut f64) -> bool>(
+        &self,
+        ray: &Ray,
+        max_distance: &mut f64,
+        mut visit: F,
+    ) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let inverse_direction = ray.direction.map(|x| 1. / x);
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(node_index) = stack.pop() {
+            let node: &BvhNode = &self.nodes[node_index];
+            if node
+                .bounds
+                .intersection_distance(ray, &inverse_direction, *max_distance)
+                .is_none()
+            {
+                continue;
+            }
+
+            if node.count > 0 {
+                for &primitive in &self.primitives[node.offset..node.offset + node.count] {
+                    if visit(primitive, max_distance) {
+                        return true;
+                    }
+                }
+            } else if ray.direction[node.axis] < 0. {
+                stack.push(node_index + 1);
+                stack.push(node.offset);
+            } else {
+                stack.push(node.offset);
+                stack.push(node_index + 1);
+            }
+        }
+
+        false
+    }
+}
@@ -1,10 +1,11 @@
-use crate::{Aperture, Ray};
-
-use rand::{thread_rng, Rng};
+use crate::{
+    filter::{BoxFilter, Filter},
+    Aperture, Ray, Sampler,
+};
 
 use nalgebra as na;
 
-use na::{Isometry3, Perspective3, Point3, Vector3};
+use na::{Isometry3, Perspective3, Point3, Vector2, Vector3};
 
 pub struct Camera {
     pub perspective: Perspective3<f64>,
@@ -14,6 +15,7 @@ pub struct Camera {
     pub aspect: f64,
     pub aperture: Box<dyn Aperture>,
     pub focal_length: f64,
+    pub filter: Box<dyn Filter>,
     frustrum_data: FrustrumData,
 }
 
@@ -122,9 +124,17 @@ impl Camera {
             aspect,
             aperture: Box::new(aperture),
             focal_length: focal_distance,
+            filter: Box::new(BoxFilter::default()),
             frustrum_data,
         }
     }
+
+    /// Spreads the samples of each pixel with `filter` instead of a box over the pixel.
+    pub fn with_filter<F: Filter + 'static>(mut self, filter: F) -> Self {
+        self.filter = Box::new(filter);
+        self
+    }
+
     pub fn new_at_origin<Ap: Aperture + Sync + 'static>(
         width: u32,
         height: u32,
@@ -148,11 +158,124 @@ impl Camera {
         )
     }
 
-    pub fn get_ray(&self, x_index: u32, y_index: u32) -> Ray {
-        let mut rng = thread_rng();
-        //Sample randomly from the area of the pixel for anti-aliasing
-        let x: f64 = x_index as f64 + rng.gen::<f64>() - 0.5;
-        let y: f64 = y_index as f64 + rng.gen::<f64>() - 0.5;
+    /// The position of the pinhole in world space.
+    pub fn position(&self) -> Point3<f64> {
+        self.translation_and_rotation.translation.vector.into()
+    }
+
+    /// Whether all camera rays pass through [`Camera::position`], which is needed to connect
+    /// light paths to the camera.
+    pub fn is_pinhole(&self) -> bool {
+        self.aperture.is_pinhole()
+    }
+
+    /// The area of the image plane at unit distance from the camera, up to the outer edges of
+    /// the outermost pixels.
+    pub fn film_area(&self) -> f64 {
+        let half_width = self.frustrum_data.near_half_width / self.frustrum_data.znear
+            * self.width as f64
+            / (self.width - 1) as f64;
+        let half_height = self.frustrum_data.near_half_height / self.frustrum_data.znear
+            * self.height as f64
+            / (self.height - 1) as f64;
+        4. * half_width * half_height
+    }
+
+    /// Continuous pixel coordinates of a camera space direction, if it is at most `margin`
+    /// pixels outside of the centers of the outermost pixels.
+    fn raster_coordinates(
+        &self,
+        local_direction: &Vector3<f64>,
+        margin: f64,
+    ) -> Option<(f64, f64)> {
+        if local_direction.z >= 0. {
+            return None;
+        }
+
+        let x = local_direction.x / -local_direction.z * self.frustrum_data.znear
+            / self.frustrum_data.near_half_width;
+        let y = local_direction.y / -local_direction.z * self.frustrum_data.znear
+            / self.frustrum_data.near_half_height;
+
+        // Inverse of the normalization in get_ray.
+        let x = (x + 1.) / 2. * (self.width - 1) as f64;
+        let y = (1. - y) / 2. * (self.height - 1) as f64;
+
+        if (-margin..self.width as f64 - 1. + margin).contains(&x)
+            && (-margin..self.height as f64 - 1. + margin).contains(&y)
+        {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// How far outside of the centers of the outermost pixels camera rays reach, in pixels.
+    fn margin(&self) -> f64 {
+        self.filter.radius().max(0.5)
+    }
+
+    /// Continuous pixel coordinates at which a world space point is seen through the pinhole,
+    /// if camera rays of any pixel reach it.
+    pub fn raster_position(&self, point: &Point3<f64>) -> Option<Vector2<f64>> {
+        let local_point = self.translation_and_rotation.inverse_transform_point(point);
+        if -local_point.z < self.frustrum_data.znear {
+            return None;
+        }
+
+        self.raster_coordinates(&local_point.coords, self.margin())
+            .map(|(x, y)| Vector2::new(x, y))
+    }
+
+    /// The pixels whose filter covers the continuous pixel coordinates `position`, with the
+    /// weight of the filter there.
+    pub fn pixel_weights(&self, position: &Vector2<f64>) -> Vec<((u32, u32), f64)> {
+        let radius = self.filter.radius();
+        let first = position.map(|x| (x - radius).ceil().max(0.) as u32);
+        let last = Vector2::new(
+            (position.x + radius).floor().min(self.width as f64 - 1.),
+            (position.y + radius).floor().min(self.height as f64 - 1.),
+        );
+
+        let mut weights = Vec::new();
+        for x in first.x..=last.x as u32 {
+            for y in first.y..=last.y as u32 {
+                let offset = position - Vector2::new(x as f64, y as f64);
+                let weight = self.filter.evaluate(offset);
+                if weight > 0. {
+                    weights.push(((x, y), weight));
+                }
+            }
+        }
+        weights
+    }
+
+    /// Solid angle density of a pinhole camera ray in the given world space direction, where
+    /// the ray is sampled uniformly over the whole image.
+    pub fn direction_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        let local_direction = self
+            .translation_and_rotation
+            .inverse_transform_vector(direction)
+            .normalize();
+
+        // Filters wider than a pixel spread some rays past the edge of the image, these are
+        // given the density of the rays inside it.
+        if self
+            .raster_coordinates(&local_direction, self.margin())
+            .is_some()
+        {
+            let cosine = -local_direction.z;
+            1. / (self.film_area() * cosine.powi(3))
+        } else {
+            0.
+        }
+    }
+
+    pub fn get_ray(&self, x_index: u32, y_index: u32, sampler: &mut dyn Sampler) -> Ray {
+        //Spread the samples around the pixel center for anti-aliasing
+        let offset = self.filter.sample_offset(sampler.get_2d());
+        let x: f64 = x_index as f64 + offset.x;
+        let y: f64 = y_index as f64 + offset.y;
 
         //Normalize the coordinates
         let x = 2. * (x / (self.width - 1) as f64) - 1.;
@@ -160,7 +283,9 @@ impl Camera {
 
         let source_ray = self.frustrum_data.get_ray_from_normalized_coordinates(x, y);
 
-        let local_ray = self.aperture.sample_ray(&source_ray, self.focal_length);
+        let local_ray = self
+            .aperture
+            .sample_ray(&source_ray, self.focal_length, sampler.get_2d());
 
         local_ray.transform_isometry(&self.translation_and_rotation)
     }
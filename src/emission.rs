@@ -0,0 +1,157 @@
+use std::{
+    f64::consts::{PI, TAU},
+    sync::Arc,
+};
+
+use nalgebra as na;
+
+use na::{Vector2, Vector3};
+
+use crate::{
+    bsdf::{cosine_sample_hemisphere, flip_z, ShadingFrame},
+    Shader, ShadingPoint,
+};
+
+mod ies;
+
+pub use ies::{IesError, IesProfile};
+
+/// How the emitted light is distributed over directions, relative to the normal of the emitter.
+#[derive(Debug, Clone, Default)]
+pub enum EmissionProfile {
+    /// The same radiance in every direction, like a diffuse surface.
+    #[default]
+    Lambertian,
+    /// Full radiance within a cone around the normal, falling off smoothly to nothing at a
+    /// wider cone. The cones are stored as the cosines of their half angles.
+    Spot { cos_inner: f64, cos_outer: f64 },
+    /// A measured distribution from a photometric file, aimed along the normal.
+    Ies(Arc<IesProfile>),
+}
+
+impl EmissionProfile {
+    /// A spot profile from the half angles of the inner and outer cones, in radians.
+    pub fn spot(inner_angle: f64, outer_angle: f64) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        Self::Spot {
+            cos_inner: inner_angle.cos(),
+            cos_outer: outer_angle.cos(),
+        }
+    }
+
+    pub fn ies(profile: IesProfile) -> Self {
+        Self::Ies(Arc::new(profile))
+    }
+
+    /// Relative intensity between 0 and 1 towards `direction`, given in a local frame with the
+    /// normal along +z.
+    pub fn intensity(&self, direction: &Vector3<f64>) -> f64 {
+        match self {
+            EmissionProfile::Lambertian => 1.,
+            EmissionProfile::Spot {
+                cos_inner,
+                cos_outer,
+            } => {
+                if direction.z >= *cos_inner {
+                    1.
+                } else if direction.z <= *cos_outer {
+                    0.
+                } else {
+                    let t = (direction.z - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3. - 2. * t)
+                }
+            }
+            EmissionProfile::Ies(profile) => {
+                let theta = direction.z.clamp(-1., 1.).acos();
+                let phi = direction.y.atan2(direction.x).rem_euclid(TAU);
+                profile.intensity(theta, phi)
+            }
+        }
+    }
+}
+
+/// Light emitted by a surface, a color given by a shader scaled by a strength. The color is
+/// usually kept in [0, 1] while the strength sets the brightness. Emitters are one-sided by
+/// default, only emitting on the side their normal points to.
+#[derive(Clone)]
+pub struct Emission {
+    color: Arc<dyn Shader>,
+    strength: f64,
+    two_sided: bool,
+    profile: EmissionProfile,
+}
+
+impl Emission {
+    pub fn new<S: Shader + 'static>(color: S, strength: f64) -> Self {
+        Self {
+            color: Arc::new(color),
+            strength,
+            two_sided: false,
+            profile: EmissionProfile::Lambertian,
+        }
+    }
+
+    /// Emits the same light from both sides of the surface.
+    pub fn two_sided(mut self) -> Self {
+        self.two_sided = true;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: EmissionProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn strength(&self) -> f64 {
+        self.strength
+    }
+
+    pub fn is_two_sided(&self) -> bool {
+        self.two_sided
+    }
+
+    pub fn profile(&self) -> &EmissionProfile {
+        &self.profile
+    }
+
+    /// Radiance emitted at `point` towards `direction`.
+    pub fn radiance(&self, point: &ShadingPoint, direction: &Vector3<f64>) -> Vector3<f64> {
+        let cosine = point.normal.dot(direction);
+        if cosine == 0. || (cosine < 0. && !self.two_sided) {
+            return Vector3::zeros();
+        }
+
+        let local = ShadingFrame::new(&point.normal).to_local(direction);
+        let intensity = self.profile.intensity(&flip_z(&local, cosine < 0.));
+        if intensity <= 0. {
+            return Vector3::zeros();
+        }
+
+        self.color.shade(point) * self.strength * intensity
+    }
+
+    /// Samples a direction to emit light in with a cosine-weighted distribution around the
+    /// normal, on either side for two-sided emitters.
+    pub fn sample_direction(&self, normal: &Vector3<f64>, u: Vector2<f64>) -> Vector3<f64> {
+        let (u, flip) = if self.two_sided {
+            let flip = u.x >= 0.5;
+            let x = if flip { 2. * u.x - 1. } else { 2. * u.x };
+            (Vector2::new(x, u.y), flip)
+        } else {
+            (u, false)
+        };
+
+        let local = flip_z(&cosine_sample_hemisphere(u), flip);
+        ShadingFrame::new(normal).to_world(&local)
+    }
+
+    /// Solid angle density with which [`Emission::sample_direction`] picks `direction`.
+    pub fn direction_pdf(&self, normal: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        let cosine = normal.dot(direction);
+        if self.two_sided {
+            cosine.abs() / TAU
+        } else {
+            cosine.max(0.) / PI
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+#[derive(Debug)]
+pub enum IesError {
+    Io(io::Error),
+    Parse(String),
+}
+
+impl Display for IesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IesError::Io(error) => write!(f, "Could not read file: {error}"),
+            IesError::Parse(message) => write!(f, "Invalid IES file: {message}"),
+        }
+    }
+}
+
+impl Error for IesError {}
+
+impl From<io::Error> for IesError {
+    fn from(error: io::Error) -> Self {
+        IesError::Io(error)
+    }
+}
+
+fn parse_error(message: impl Into<String>) -> IesError {
+    IesError::Parse(message.into())
+}
+
+/// A luminous intensity distribution read from an IESNA LM-63 photometric file, with type C
+/// photometry. Vertical angles start at the nadir, the direction the luminaire points at, and
+/// horizontal angles go around it. Intensities are normalized so the brightest direction is 1.
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    /// In radians, ascending.
+    vertical_angles: Vec<f64>,
+    /// In radians, ascending.
+    horizontal_angles: Vec<f64>,
+    /// One row of vertical samples per horizontal angle.
+    intensities: Vec<f64>,
+}
+
+impl IesProfile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IesError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, IesError> {
+        let mut lines = source.lines();
+
+        // Skip the version line and keywords up to the tilt specification.
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find_map(|line| line.strip_prefix("TILT="))
+            .ok_or_else(|| parse_error("Missing TILT line"))?;
+
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                token
+                    .parse::<f64>()
+                    .map_err(|_| parse_error(format!("Invalid number \"{token}\"")))
+            });
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(parse_error("Unexpected end of file")))
+        };
+
+        match tilt.trim() {
+            "NONE" => {}
+            "INCLUDE" => {
+                // Lamp to luminaire geometry, then the tilt angles and their factors, which only
+                // matter for lamps mounted at an angle.
+                next()?;
+                let count = next()? as usize;
+                for _ in 0..2 * count {
+                    next()?;
+                }
+            }
+            _ => return Err(parse_error("External tilt files are not supported")),
+        }
+
+        let _lamp_count = next()?;
+        let _lumens_per_lamp = next()?;
+        let _multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()?;
+        // Units and luminous opening dimensions, ballast factor, a reserved value and input
+        // watts.
+        for _ in 0..7 {
+            next()?;
+        }
+
+        if photometric_type != 1. {
+            return Err(parse_error("Only type C photometry is supported"));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(parse_error("No angles given"));
+        }
+
+        let vertical_angles = (0..vertical_count)
+            .map(|_| next().map(f64::to_radians))
+            .collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..horizontal_count)
+            .map(|_| next().map(f64::to_radians))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut intensities = (0..vertical_count * horizontal_count)
+            .map(|_| next())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let ascending = |angles: &[f64]| angles.windows(2).all(|pair| pair[0] < pair[1]);
+        if !ascending(&vertical_angles) || !ascending(&horizontal_angles) {
+            return Err(parse_error("Angles must be in ascending order"));
+        }
+
+        let max = intensities.iter().cloned().fold(0., f64::max);
+        if max > 0. {
+            intensities
+                .iter_mut()
+                .for_each(|intensity| *intensity /= max);
+        }
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            intensities,
+        })
+    }
+
+    /// Relative intensity at the angle `theta` from the nadir and `phi` around it, in radians.
+    pub fn intensity(&self, theta: f64, phi: f64) -> f64 {
+        let Some((v, tv)) = interpolation(&self.vertical_angles, theta) else {
+            return 0.;
+        };
+
+        let phi = self.fold_horizontal(phi);
+        let (h, th) = interpolation(&self.horizontal_angles, phi).unwrap_or_else(|| {
+            if phi < self.horizontal_angles[0] {
+                (0, 0.)
+            } else {
+                (self.horizontal_angles.len() - 1, 0.)
+            }
+        });
+
+        let vertical_count = self.vertical_angles.len();
+        let value = |h: usize, v: usize| {
+            let h = h.min(self.horizontal_angles.len() - 1);
+            let v = v.min(vertical_count - 1);
+            self.intensities[h * vertical_count + v]
+        };
+
+        let lower = value(h, v) * (1. - tv) + value(h, v + 1) * tv;
+        let upper = value(h + 1, v) * (1. - tv) + value(h + 1, v + 1) * tv;
+        lower * (1. - th) + upper * th
+    }
+
+    /// Maps `phi` into the range covered by the horizontal angles, using the symmetry that the
+    /// last angle implies.
+    fn fold_horizontal(&self, phi: f64) -> f64 {
+        let phi = phi.to_degrees().rem_euclid(360.);
+        let last = self.horizontal_angles[self.horizontal_angles.len() - 1].to_degrees();
+
+        let folded = if last <= 0. {
+            // Rotationally symmetric
+            0.
+        } else if last <= 90. {
+            // Symmetric in each quadrant
+            let phi = if phi > 180. { 360. - phi } else { phi };
+            if phi > 90. {
+                180. - phi
+            } else {
+                phi
+            }
+        } else if last <= 180. {
+            // Symmetric about the 0-180 degree plane
+            if phi > 180. {
+                360. - phi
+            } else {
+                phi
+            }
+        } else {
+            phi
+        };
+
+        folded.to_radians()
+    }
+}
+
+/// The index of the interval of `angles` containing `x` and the position within it, or `None`
+/// when `x` is outside of the covered range.
+fn interpolation(angles: &[f64], x: f64) -> Option<(usize, f64)> {
+    const EPSILON: f64 = 1e-9;
+
+    let first = angles[0];
+    let last = angles[angles.len() - 1];
+    if x < first - EPSILON || x > last + EPSILON {
+        return None;
+    }
+    if angles.len() == 1 {
+        return Some((0, 0.));
+    }
+
+    let index = angles
+        .partition_point(|angle| *angle <= x)
+        .clamp(1, angles.len() - 1)
+        - 1;
+    let t = ((x - angles[index]) / (angles[index + 1] - angles[index])).clamp(0., 1.);
+    Some((index, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An LM-63 file with type C photometry, angles in degrees and one row of intensities per
+    /// horizontal angle.
+    fn ies_source(vertical: &[f64], horizontal: &[f64], intensities: &[f64]) -> String {
+        let join = |values: &[f64]| {
+            values
+                .iter()
+                .map(f64::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        format!(
+            "IESNA:LM-63-2002\n[TEST] profile\nTILT=NONE\n1 1000 1 {} {} 1 2 0 0 0\n1 1 100\n{}\n{}\n{}\n",
+            vertical.len(),
+            horizontal.len(),
+            join(vertical),
+            join(horizontal),
+            join(intensities),
+        )
+    }
+
+    fn assert_intensity(profile: &IesProfile, theta_degrees: f64, phi_degrees: f64, expected: f64) {
+        let intensity = profile.intensity(theta_degrees.to_radians(), phi_degrees.to_radians());
+        assert!(
+            (intensity - expected).abs() < 1e-9,
+            "intensity at ({theta_degrees}, {phi_degrees}) is {intensity} instead of {expected}"
+        );
+    }
+
+    #[test]
+    fn rotationally_symmetric_profiles_ignore_phi() {
+        let source = ies_source(&[0., 45., 90.], &[0.], &[200., 100., 0.]);
+        let profile = IesProfile::parse(&source).unwrap();
+
+        for phi in [0., 123., 270.] {
+            assert_intensity(&profile, 0., phi, 1.);
+            assert_intensity(&profile, 22.5, phi, 0.75);
+            assert_intensity(&profile, 45., phi, 0.5);
+        }
+    }
+
+    #[test]
+    fn intensity_is_zero_outside_the_vertical_range() {
+        let source = ies_source(&[0., 45., 90.], &[0.], &[200., 100., 50.]);
+        let profile = IesProfile::parse(&source).unwrap();
+
+        assert_intensity(&profile, 90., 0., 0.25);
+        assert_intensity(&profile, 91., 0., 0.);
+        assert_intensity(&profile, 180., 0., 0.);
+        assert_intensity(&profile, -1., 0., 0.);
+    }
+
+    #[test]
+    fn quadrant_symmetric_profiles_are_mirrored() {
+        let source = ies_source(&[0., 90.], &[0., 90.], &[100., 100., 50., 50.]);
+        let profile = IesProfile::parse(&source).unwrap();
+
+        assert_intensity(&profile, 30., 45., 0.75);
+        assert_intensity(&profile, 30., 90., 0.5);
+        assert_intensity(&profile, 30., 135., 0.75);
+        assert_intensity(&profile, 30., 180., 1.);
+        assert_intensity(&profile, 30., 270., 0.5);
+        assert_intensity(&profile, 30., 315., 0.75);
+    }
+
+    #[test]
+    fn half_profiles_are_mirrored_at_the_plane_of_symmetry() {
+        let source = ies_source(
+            &[0., 90.],
+            &[0., 90., 180.],
+            &[100., 100., 50., 50., 0., 0.],
+        );
+        let profile = IesProfile::parse(&source).unwrap();
+
+        assert_intensity(&profile, 60., 135., 0.25);
+        assert_intensity(&profile, 60., 180., 0.);
+        assert_intensity(&profile, 60., 225., 0.25);
+        assert_intensity(&profile, 60., 270., 0.5);
+    }
+
+    #[test]
+    fn full_profiles_are_interpolated_all_around() {
+        let source = ies_source(
+            &[0., 90.],
+            &[0., 90., 180., 270., 360.],
+            &[100., 100., 75., 75., 50., 50., 25., 25., 100., 100.],
+        );
+        let profile = IesProfile::parse(&source).unwrap();
+
+        assert_intensity(&profile, 10., 90., 0.75);
+        assert_intensity(&profile, 10., 270., 0.25);
+        assert_intensity(&profile, 10., 315., 0.625);
+        assert_intensity(&profile, 10., -45., 0.625);
+    }
+
+    #[test]
+    fn truncated_files_fail() {
+        let source = ies_source(&[0., 90.], &[0., 90.], &[100., 100., 50.]);
+        assert!(IesProfile::parse(&source).is_err());
+
+        let source = ies_source(&[0., 90.], &[0.], &[100., 50.]);
+        let header_only = &source[..source.find("TILT=NONE").unwrap() + 10];
+        assert!(IesProfile::parse(header_only).is_err());
+        assert!(IesProfile::parse("IESNA:LM-63-2002\n[TEST] profile\n").is_err());
+    }
+}
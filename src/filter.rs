@@ -0,0 +1,148 @@
+use nalgebra::Vector2;
+
+use crate::function_approximation::PiecewiseConstant;
+
+/// Number of bins the Gaussian filter is tabulated with.
+const GAUSSIAN_RESOLUTION: usize = 64;
+
+/// Reconstruction filter that spreads the samples of a pixel around its center. Samples are
+/// placed in proportion to the filter, so they all count the same. Light paths that
+/// [`BDPTRenderer`](crate::renderer::BDPTRenderer) connects to the camera are spread over the
+/// pixels around them, weighted by [`Filter::evaluate`].
+pub trait Filter: Send + Sync {
+    /// Maps a point in [0, 1)² to an offset from the pixel center, in pixels.
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64>;
+
+    /// Density with which [`Filter::sample_offset`] places a sample at `offset` from the pixel
+    /// center, per square pixel.
+    fn evaluate(&self, offset: Vector2<f64>) -> f64;
+
+    /// How far the filter reaches from the pixel center, in pixels.
+    fn radius(&self) -> f64;
+}
+
+/// Spreads samples evenly over a square, with a radius of 0.5 it covers exactly the pixel.
+#[derive(Debug, Clone, Copy)]
+pub struct BoxFilter {
+    pub radius: f64,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Default for BoxFilter {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl Filter for BoxFilter {
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64> {
+        u.map(|u| (2. * u - 1.) * self.radius)
+    }
+
+    fn evaluate(&self, offset: Vector2<f64>) -> f64 {
+        if offset.abs().max() <= self.radius {
+            1. / (4. * self.radius * self.radius)
+        } else {
+            0.
+        }
+    }
+
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+/// Falls off linearly from the pixel center, which blurs a little but hides aliasing better
+/// than the box filter.
+#[derive(Debug, Clone, Copy)]
+pub struct TriangleFilter {
+    pub radius: f64,
+}
+
+impl TriangleFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Default for TriangleFilter {
+    fn default() -> Self {
+        Self::new(1.)
+    }
+}
+
+impl Filter for TriangleFilter {
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64> {
+        u.map(|u| {
+            if u < 0.5 {
+                self.radius * ((2. * u).sqrt() - 1.)
+            } else {
+                self.radius * (1. - (2. - 2. * u).sqrt())
+            }
+        })
+    }
+
+    fn evaluate(&self, offset: Vector2<f64>) -> f64 {
+        offset
+            .map(|x| (self.radius - x.abs()).max(0.) / (self.radius * self.radius))
+            .product()
+    }
+
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+/// A Gaussian with standard deviation `sigma`, cut off at `radius` and shifted down to reach
+/// zero there.
+#[derive(Debug, Clone)]
+pub struct GaussianFilter {
+    pub radius: f64,
+    pub sigma: f64,
+    distribution: PiecewiseConstant,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: f64, sigma: f64) -> Self {
+        let gaussian = |x: f64| (-x * x / (2. * sigma * sigma)).exp();
+        let values: Vec<f64> = (0..GAUSSIAN_RESOLUTION)
+            .map(|i| {
+                let x = ((i as f64 + 0.5) / GAUSSIAN_RESOLUTION as f64 * 2. - 1.) * radius;
+                (gaussian(x) - gaussian(radius)).max(0.)
+            })
+            .collect();
+
+        Self {
+            radius,
+            sigma,
+            distribution: PiecewiseConstant::build(&values),
+        }
+    }
+}
+
+impl Default for GaussianFilter {
+    fn default() -> Self {
+        Self::new(1.5, 0.5)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn sample_offset(&self, u: Vector2<f64>) -> Vector2<f64> {
+        u.map(|u| (2. * self.distribution.sample_at(u).0 - 1.) * self.radius)
+    }
+
+    fn evaluate(&self, offset: Vector2<f64>) -> f64 {
+        offset
+            .map(|x| self.distribution.pdf((x / self.radius + 1.) / 2.) / (2. * self.radius))
+            .product()
+    }
+
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
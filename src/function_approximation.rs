@@ -167,7 +167,140 @@ impl ProbabilityDensityFunction {
     }
 
     pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
-        let x = rng.gen();
-        self.inverse_cdf.apply(x).unwrap()
+        self.sample_at(rng.gen())
+    }
+
+    /// Maps a uniformly distributed `u` in [0, 1) to a sample of this distribution.
+    pub fn sample_at(&self, u: f64) -> f64 {
+        self.inverse_cdf.apply(u).unwrap()
+    }
+}
+
+/// A density over [0, 1) that is constant within each of a number of equally sized bins,
+/// proportional to the given values. Unlike [`ProbabilityDensityFunction`] sampling it is exact,
+/// which suits tabulated data such as images.
+#[derive(Debug, Clone)]
+pub struct PiecewiseConstant {
+    values: Vec<f64>,
+    /// Cumulative distribution at the bin edges, one more entry than there are values.
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl PiecewiseConstant {
+    /// Falls back to a uniform density when all values are zero.
+    pub fn build(values: &[f64]) -> Self {
+        assert!(!values.is_empty(), "Need at least one value");
+        let values: Vec<f64> = values.iter().map(|value| value.abs()).collect();
+        let count = values.len() as f64;
+
+        let mut cdf = vec![0.; values.len() + 1];
+        for i in 0..values.len() {
+            cdf[i + 1] = cdf[i] + values[i] / count;
+        }
+        let integral = cdf[values.len()];
+
+        if integral > 0. {
+            cdf.iter_mut().for_each(|x| *x /= integral);
+        } else {
+            for (i, x) in cdf.iter_mut().enumerate() {
+                *x = i as f64 / count;
+            }
+        }
+
+        Self {
+            values,
+            cdf,
+            integral,
+        }
+    }
+
+    /// The integral of the original values over [0, 1).
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Maps a uniformly distributed `u` in [0, 1) to a sample, returns it with its density and
+    /// the index of its bin.
+    pub fn sample_at(&self, u: f64) -> (f64, f64, usize) {
+        let index = self
+            .cdf
+            .partition_point(|x| *x <= u)
+            .clamp(1, self.values.len())
+            - 1;
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0. {
+            (u - self.cdf[index]) / width
+        } else {
+            0.
+        };
+        let x = ((index as f64 + offset) / self.len() as f64).min(1. - f64::EPSILON);
+        (x, self.bin_pdf(index), index)
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        if !(0. ..1.).contains(&x) {
+            return 0.;
+        }
+        self.bin_pdf(((x * self.len() as f64) as usize).min(self.len() - 1))
+    }
+
+    fn bin_pdf(&self, index: usize) -> f64 {
+        if self.integral > 0. {
+            self.values[index] / self.integral
+        } else {
+            1.
+        }
+    }
+}
+
+/// A piecewise-constant density over [0, 1)², built from a grid of values with `width` columns
+/// along x and `height` rows along y, stored row by row. Samples pick a row from the marginal
+/// density of y and then a column from that row.
+#[derive(Debug, Clone)]
+pub struct PiecewiseConstant2D {
+    conditionals: Vec<PiecewiseConstant>,
+    marginal: PiecewiseConstant,
+}
+
+impl PiecewiseConstant2D {
+    pub fn build(values: &[f64], width: usize, height: usize) -> Self {
+        assert_eq!(
+            values.len(),
+            width * height,
+            "Expected {width}x{height} values"
+        );
+
+        let conditionals: Vec<_> = values.chunks(width).map(PiecewiseConstant::build).collect();
+        let row_integrals: Vec<_> = conditionals.iter().map(|row| row.integral()).collect();
+
+        Self {
+            conditionals,
+            marginal: PiecewiseConstant::build(&row_integrals),
+        }
+    }
+
+    /// Maps uniformly distributed `u` and `v` in [0, 1) to a point, returns it with its density.
+    pub fn sample_at(&self, u: f64, v: f64) -> ((f64, f64), f64) {
+        let (y, pdf_y, row) = self.marginal.sample_at(v);
+        let (x, pdf_x, _) = self.conditionals[row].sample_at(u);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        if !(0. ..1.).contains(&y) {
+            return 0.;
+        }
+        let row = ((y * self.conditionals.len() as f64) as usize).min(self.conditionals.len() - 1);
+        self.marginal.pdf(y) * self.conditionals[row].pdf(x)
     }
 }
@@ -1,32 +1,60 @@
 use nalgebra::{Point3, Vector3};
 
 pub mod aperture;
+pub mod bounding_box;
+pub mod bsdf;
+pub mod bvh;
 pub mod camera;
+pub mod emission;
+pub mod filter;
 pub mod function_approximation;
+pub mod light;
+pub mod loader;
 pub mod material;
 pub mod object;
 pub mod ray;
 pub mod render_buffer;
 pub mod renderer;
+pub mod sampler;
 pub mod scene;
 pub mod shader;
 pub mod shape;
 
 pub use aperture::Aperture;
+pub use bounding_box::BoundingBox;
+pub use bsdf::Bsdf;
 pub use camera::Camera;
+pub use emission::Emission;
+pub use light::Light;
 pub use material::Material;
 pub use object::Object;
 pub use ray::Ray;
 pub use render_buffer::RenderBuffer;
 pub use renderer::{BackwardRenderer, Renderer};
+pub use sampler::Sampler;
 pub use scene::Scene;
-pub use shader::Shader;
+pub use shader::{Shader, ShadingPoint};
 pub use shape::{Inverted, Shape, Sphere};
 
 pub fn reflect(incoming: &Vector3<f64>, normal: &Vector3<f64>) -> Vector3<f64> {
     incoming - 2. * normal.dot(incoming) * normal
 }
 
+/// Builds two tangent vectors that together with the unit `normal` form an orthonormal basis.
+pub fn orthonormal_basis(normal: &Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    // Duff et al., "Building an Orthonormal Basis, Revisited"
+    let sign = 1f64.copysign(normal.z);
+    let a = -1. / (sign + normal.z);
+    let b = normal.x * normal.y * a;
+    let tangent = Vector3::new(
+        1. + sign * normal.x * normal.x * a,
+        sign * b,
+        -sign * normal.x,
+    );
+    let bitangent = Vector3::new(b, sign + normal.y * normal.y * a, -normal.y);
+    (tangent, bitangent)
+}
+
 pub fn find_normal(incoming: &Vector3<f64>, outgoing: &Vector3<f64>) -> Vector3<f64> {
     (outgoing - incoming).normalize()
 }
@@ -36,9 +64,3 @@ pub struct Hit {
     pub position: Point3<f64>,
     pub normal: Vector3<f64>,
 }
-
-#[derive(Clone, Copy, Debug)]
-pub struct PointLight {
-    pub position: Point3<f64>,
-    pub color: Vector3<f64>,
-}
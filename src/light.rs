@@ -0,0 +1,473 @@
+use std::f64::consts::{PI, TAU};
+
+use nalgebra as na;
+
+use na::{Point3, Vector2, Vector3};
+
+use crate::{
+    bsdf::ShadingFrame,
+    emission::EmissionProfile,
+    sampler::{IndependentSampler, Sampler},
+    shader::luminance,
+    Object, Ray, Scene,
+};
+
+mod environment;
+mod sky;
+
+pub use environment::EnvironmentLight;
+pub use sky::PreethamSky;
+
+/// Number of samples used to estimate the power of lights with non-uniform emission.
+const POWER_SAMPLES: usize = 256;
+
+/// Samples a direction uniformly within the cone of directions whose angle to +z has a cosine
+/// of at least `cos_max`. A `cos_max` of -1 covers the whole sphere.
+fn sample_uniform_cone(u: Vector2<f64>, cos_max: f64) -> Vector3<f64> {
+    let cos_theta = 1. - u.x * (1. - cos_max);
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = u.y * TAU;
+    Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+fn uniform_cone_pdf(cos_max: f64) -> f64 {
+    1. / (TAU * (1. - cos_max))
+}
+
+/// Samples the origin of a ray coming from `to_light`, on a disk facing that direction just
+/// outside of the bounding sphere of the scene. Returns the origin and its area density.
+pub(crate) fn sample_disk_outside(
+    scene: &Scene,
+    to_light: &Vector3<f64>,
+    u: Vector2<f64>,
+) -> (Point3<f64>, f64) {
+    let (center, radius) = scene.bounding_sphere();
+    let r = u.x.sqrt() * radius;
+    let phi = u.y * TAU;
+    let offset =
+        ShadingFrame::new(to_light).to_world(&Vector3::new(r * phi.cos(), r * phi.sin(), radius));
+    (center + offset, 1. / (PI * radius * radius))
+}
+
+/// Light arriving at a point from a sampled light.
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Direction from the receiving point towards the light.
+    pub direction: Vector3<f64>,
+    /// Distance to the light, infinite for directional lights.
+    pub distance: f64,
+    /// The incident radiance. For point lights this is the intensity divided by the squared
+    /// distance, for directional lights the irradiance spread over the sampled cone.
+    pub radiance: Vector3<f64>,
+    /// Solid angle density of `direction`, or 1 for a single possible direction.
+    pub pdf: f64,
+    /// Set when rays can't hit the light, so sampling the light is the only way to find it and
+    /// it must not be weighted against BSDF sampling.
+    pub delta: bool,
+}
+
+/// A light source at a single point, such as a bare bulb or a spot light. The emission can be
+/// shaped by a profile aimed along `direction`.
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    position: Point3<f64>,
+    frame: ShadingFrame,
+    intensity: Vector3<f64>,
+    profile: EmissionProfile,
+}
+
+impl PointLight {
+    /// A light that emits equally in all directions, `strength` sets the radiant intensity.
+    pub fn new(position: Point3<f64>, color: Vector3<f64>, strength: f64) -> Self {
+        Self {
+            position,
+            frame: ShadingFrame::new(&Vector3::z()),
+            intensity: color * strength,
+            profile: EmissionProfile::Lambertian,
+        }
+    }
+
+    /// A light that shines along `direction` with full intensity within `inner_angle` and falls
+    /// off to nothing at `outer_angle`, both half angles in radians.
+    pub fn spot(
+        position: Point3<f64>,
+        direction: Vector3<f64>,
+        color: Vector3<f64>,
+        strength: f64,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        Self::new(position, color, strength)
+            .with_profile(direction, EmissionProfile::spot(inner_angle, outer_angle))
+    }
+
+    /// Shapes the emission by `profile`, for example a measured IES profile, aimed at
+    /// `direction`.
+    pub fn with_profile(mut self, direction: Vector3<f64>, profile: EmissionProfile) -> Self {
+        self.frame = ShadingFrame::new(&direction.normalize());
+        self.profile = profile;
+        self
+    }
+
+    pub fn position(&self) -> Point3<f64> {
+        self.position
+    }
+
+    /// Radiant intensity towards `direction`.
+    pub fn intensity(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        self.intensity * self.profile.intensity(&self.frame.to_local(direction))
+    }
+
+    /// The cosine of the widest angle to the aim direction that receives any light.
+    fn cos_max(&self) -> f64 {
+        match self.profile {
+            EmissionProfile::Spot { cos_outer, .. } => cos_outer,
+            _ => -1.,
+        }
+    }
+
+    /// Samples a direction to emit light in, uniformly over the directions that receive light.
+    pub fn sample_direction(&self, u: Vector2<f64>) -> Vector3<f64> {
+        self.frame.to_world(&sample_uniform_cone(u, self.cos_max()))
+    }
+
+    /// Solid angle density with which [`PointLight::sample_direction`] picks `direction`.
+    pub fn direction_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        let cos_max = self.cos_max();
+        if self.frame.to_local(direction).z < cos_max {
+            0.
+        } else {
+            uniform_cone_pdf(cos_max)
+        }
+    }
+
+    /// Total emitted power, estimated by integrating the intensity over all directions.
+    pub fn power(&self) -> f64 {
+        let mut sampler = IndependentSampler::new(POWER_SAMPLES, 0);
+        let total: f64 = (0..POWER_SAMPLES)
+            .map(|i| {
+                sampler.start_pixel_sample((0, 0), i);
+                let direction = self.sample_direction(sampler.get_2d());
+                luminance(&self.intensity(&direction)) / self.direction_pdf(&direction)
+            })
+            .sum();
+        total / POWER_SAMPLES as f64
+    }
+
+    fn sample_incident(&self, position: &Point3<f64>) -> Option<LightSample> {
+        let difference = self.position - position;
+        let distance = difference.magnitude();
+        if distance == 0. {
+            return None;
+        }
+        let direction = difference / distance;
+
+        let radiance = self.intensity(&-direction) / (distance * distance);
+        (radiance.max() > 0.).then_some(LightSample {
+            direction,
+            distance,
+            radiance,
+            pdf: 1.,
+            delta: true,
+        })
+    }
+}
+
+/// Parallel light from a distant source such as the sun. `strength` sets the irradiance on a
+/// surface facing the light. A non-zero angular diameter spreads the light over a disk in the
+/// sky, which gives soft shadows and lets rays that leave the scene see the light.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    /// The direction the light travels in.
+    direction: Vector3<f64>,
+    irradiance: Vector3<f64>,
+    /// Cosine of the angular radius of the light source.
+    cos_max: f64,
+}
+
+impl DirectionalLight {
+    /// `direction` is the direction the light travels in, `angular_diameter` is in radians.
+    pub fn new(
+        direction: Vector3<f64>,
+        color: Vector3<f64>,
+        strength: f64,
+        angular_diameter: f64,
+    ) -> Self {
+        Self {
+            direction: direction.normalize(),
+            irradiance: color * strength,
+            cos_max: (angular_diameter / 2.).cos(),
+        }
+    }
+
+    pub fn direction(&self) -> Vector3<f64> {
+        self.direction
+    }
+
+    pub fn irradiance(&self) -> Vector3<f64> {
+        self.irradiance
+    }
+
+    pub fn is_delta(&self) -> bool {
+        self.cos_max >= 1.
+    }
+
+    /// The irradiance spread evenly over the solid angle of the source, or the irradiance itself
+    /// for a light without a size.
+    fn emitted_radiance(&self) -> Vector3<f64> {
+        if self.is_delta() {
+            self.irradiance
+        } else {
+            self.irradiance * uniform_cone_pdf(self.cos_max)
+        }
+    }
+
+    /// Radiance arriving from `direction`, which is zero outside of the disk of the source. Rays
+    /// can't hit a light without a size, so it is always zero for those.
+    pub fn radiance(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        if self.direction_pdf(direction) > 0. {
+            self.emitted_radiance()
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    /// Solid angle density with which [`DirectionalLight::sample_direction`] picks `direction`,
+    /// or 0 for a light without a size.
+    pub fn direction_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        if self.is_delta() || -self.direction.dot(&direction.normalize()) < self.cos_max {
+            0.
+        } else {
+            uniform_cone_pdf(self.cos_max)
+        }
+    }
+
+    /// Samples a direction towards the light source, with its solid angle density.
+    pub fn sample_direction(&self, u: Vector2<f64>) -> (Vector3<f64>, f64) {
+        if self.is_delta() {
+            return (-self.direction, 1.);
+        }
+
+        let frame = ShadingFrame::new(&-self.direction);
+        let direction = frame.to_world(&sample_uniform_cone(u, self.cos_max));
+        (direction, uniform_cone_pdf(self.cos_max))
+    }
+
+    /// The power falling onto a scene bounded by a sphere of radius `scene_radius`.
+    pub fn power(&self, scene_radius: f64) -> f64 {
+        PI * scene_radius * scene_radius * luminance(&self.irradiance)
+    }
+
+    fn sample_incident(&self, u: Vector2<f64>) -> Option<LightSample> {
+        let (direction, pdf) = self.sample_direction(u);
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.emitted_radiance(),
+            pdf,
+            delta: self.is_delta(),
+        })
+    }
+}
+
+/// A light source of a scene. Emissive objects become area lights automatically, point and
+/// directional lights are added to the scene separately.
+#[derive(Debug, Clone)]
+pub enum Light {
+    /// The emissive object at this index in [`Scene::objects`].
+    Area(usize),
+    Point(PointLight),
+    Directional(DirectionalLight),
+    Environment(EnvironmentLight),
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+
+impl From<EnvironmentLight> for Light {
+    fn from(light: EnvironmentLight) -> Self {
+        Light::Environment(light)
+    }
+}
+
+impl Light {
+    /// Whether the light sits at a single point, which rays can't hit.
+    pub fn is_delta_position(&self) -> bool {
+        matches!(self, Light::Point(_))
+    }
+
+    /// Whether the light is infinitely far away. Rays that leave the scene see environment
+    /// lights and directional lights with a size.
+    pub fn is_infinite(&self) -> bool {
+        matches!(self, Light::Directional(_) | Light::Environment(_))
+    }
+
+    /// Radiance arriving from `direction` on a ray that left the scene, zero for lights that
+    /// aren't infinitely far away.
+    pub fn radiance(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        match self {
+            Light::Directional(light) => light.radiance(direction),
+            Light::Environment(light) => light.radiance(direction),
+            Light::Area(_) | Light::Point(_) => Vector3::zeros(),
+        }
+    }
+
+    /// Solid angle density with which [`Light::sample_incident`] picks `direction` on lights
+    /// infinitely far away, zero for all other lights.
+    pub fn direction_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        match self {
+            Light::Directional(light) => light.direction_pdf(direction),
+            Light::Environment(light) => light.direction_pdf(direction),
+            Light::Area(_) | Light::Point(_) => 0.,
+        }
+    }
+
+    /// Samples the light arriving at `position`.
+    pub fn sample_incident(
+        &self,
+        scene: &Scene,
+        position: &Point3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<LightSample> {
+        match self {
+            Light::Area(index) => {
+                let object = &scene.objects[*index];
+                let point = object.sample_surface_point(sampler);
+
+                let difference = point.position - position;
+                let distance = difference.magnitude();
+                let direction = difference / distance;
+
+                let radiance = object.material().emission(&point, &-direction);
+                let cosine = point.normal.dot(&direction).abs();
+                if radiance.max() <= 0. || cosine == 0. {
+                    return None;
+                }
+
+                Some(LightSample {
+                    direction,
+                    distance,
+                    radiance,
+                    pdf: distance * distance / (cosine * object.area()),
+                    delta: false,
+                })
+            }
+            Light::Point(light) => light.sample_incident(position),
+            Light::Directional(light) => light.sample_incident(sampler.get_2d()),
+            Light::Environment(light) => {
+                let (direction, pdf) = light.sample_direction(sampler.get_2d());
+                let radiance = light.radiance(&direction);
+                (pdf > 0. && radiance.max() > 0.).then_some(LightSample {
+                    direction,
+                    distance: f64::INFINITY,
+                    radiance,
+                    pdf,
+                    delta: false,
+                })
+            }
+        }
+    }
+
+    /// Samples a ray leaving the light, returns the ray, the emitted radiance or intensity
+    /// along it and the density of its origin and direction. Rays of directional and
+    /// environment lights start on a disk outside of the scene bounds.
+    pub fn sample_emission(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>, f64)> {
+        match self {
+            Light::Area(index) => {
+                let object = &scene.objects[*index];
+                let (point, ray) = object.sample_emissive_ray(sampler);
+                let emission = object.material().emission(&point, &ray.direction);
+                let pdf = object.material().emitter().map_or(0., |emitter| {
+                    emitter.direction_pdf(&point.normal, &ray.direction)
+                }) / object.area();
+                Some((ray, emission, pdf))
+            }
+            Light::Point(light) => {
+                let direction = light.sample_direction(sampler.get_2d());
+                let ray = Ray {
+                    origin: light.position,
+                    direction,
+                };
+                Some((
+                    ray,
+                    light.intensity(&direction),
+                    light.direction_pdf(&direction),
+                ))
+            }
+            Light::Directional(light) => {
+                let (to_light, pdf_direction) = light.sample_direction(sampler.get_2d());
+                let (origin, pdf_position) =
+                    sample_disk_outside(scene, &to_light, sampler.get_2d());
+
+                let ray = Ray {
+                    origin,
+                    direction: -to_light,
+                };
+                Some((ray, light.emitted_radiance(), pdf_direction * pdf_position))
+            }
+            Light::Environment(light) => {
+                let (to_light, pdf_direction) = light.sample_direction(sampler.get_2d());
+                if pdf_direction == 0. {
+                    return None;
+                }
+                let (origin, pdf_position) =
+                    sample_disk_outside(scene, &to_light, sampler.get_2d());
+
+                let ray = Ray {
+                    origin,
+                    direction: -to_light,
+                };
+                Some((ray, light.radiance(&to_light), pdf_direction * pdf_position))
+            }
+        }
+    }
+
+    /// Total emitted power, used to pick lights in proportion to their contribution. Area
+    /// lights index into `objects` and directional lights cover a sphere of radius
+    /// `scene_radius`.
+    pub fn power(&self, objects: &[Object], scene_radius: f64) -> f64 {
+        match self {
+            Light::Area(index) => {
+                let object = &objects[*index];
+                let Some(emitter) = object.material().emitter() else {
+                    return 0.;
+                };
+
+                // A fixed sequence keeps the light selection the same between renders.
+                let mut sampler = IndependentSampler::new(POWER_SAMPLES, 0);
+                let total: f64 = (0..POWER_SAMPLES)
+                    .map(|i| {
+                        sampler.start_pixel_sample((0, 0), i);
+                        let point = object.sample_surface_point(&mut sampler);
+                        let direction = emitter.sample_direction(&point.normal, sampler.get_2d());
+                        let pdf = emitter.direction_pdf(&point.normal, &direction);
+                        if pdf == 0. {
+                            return 0.;
+                        }
+                        luminance(&emitter.radiance(&point, &direction))
+                            * point.normal.dot(&direction).abs()
+                            / pdf
+                    })
+                    .sum();
+                total / POWER_SAMPLES as f64 * object.area()
+            }
+            Light::Point(light) => light.power(),
+            Light::Directional(light) => light.power(scene_radius),
+            Light::Environment(light) => light.power(scene_radius),
+        }
+    }
+}
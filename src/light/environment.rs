@@ -0,0 +1,178 @@
+use std::{
+    f64::consts::{PI, TAU},
+    path::Path,
+    sync::Arc,
+};
+
+use image::{DynamicImage, ImageError};
+use nalgebra as na;
+
+use na::{Rotation3, Vector2, Vector3};
+
+use crate::{function_approximation::PiecewiseConstant2D, shader::luminance};
+
+/// Light arriving from infinitely far away in every direction, given by an HDR image in the
+/// equirectangular projection. The top row of the image is straight up along +y and the center
+/// looks along -z. Directions are importance sampled in proportion to the brightness of the
+/// pixels.
+#[derive(Debug, Clone)]
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Arc<Vec<Vector3<f64>>>,
+    distribution: Arc<PiecewiseConstant2D>,
+    rotation: Rotation3<f64>,
+    intensity: f64,
+}
+
+impl EnvironmentLight {
+    /// Loads an equirectangular image, usually an EXR file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        let image = image::open(path)?;
+        Ok(Self::from_image(&image))
+    }
+
+    /// Floating point images are assumed to be linear already, all others are converted from
+    /// sRGB.
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let is_linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+
+        let rgb = image.to_rgb32f();
+        let pixels = rgb
+            .pixels()
+            .map(|pixel| {
+                let color = Vector3::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+                if is_linear {
+                    color
+                } else {
+                    color.map(|x| x.powf(2.2))
+                }
+            })
+            .collect();
+
+        Self::new(rgb.width() as usize, rgb.height() as usize, pixels)
+    }
+
+    /// An environment from linear pixels stored row by row, starting at the top.
+    pub fn new(width: usize, height: usize, pixels: Vec<Vector3<f64>>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "Expected {width}x{height} pixels"
+        );
+
+        // Rows near the poles cover less of the sphere.
+        let weights: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                let theta = ((i / width) as f64 + 0.5) / height as f64 * PI;
+                luminance(pixel).max(0.) * theta.sin()
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+            distribution: Arc::new(PiecewiseConstant2D::build(&weights, width, height)),
+            rotation: Rotation3::identity(),
+            intensity: 1.,
+        }
+    }
+
+    /// The same radiance from every direction.
+    pub fn uniform(color: Vector3<f64>) -> Self {
+        Self::new(1, 1, vec![color])
+    }
+
+    /// Rotates the environment around the scene.
+    pub fn with_rotation(mut self, rotation: Rotation3<f64>) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Scales the radiance of the image.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn rotation(&self) -> Rotation3<f64> {
+        self.rotation
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Radiance arriving from `direction`, so seen by a ray traveling along `direction`.
+    pub fn radiance(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        let uv = Self::equirectangular(&(self.rotation.inverse() * direction));
+        let x = ((uv.x * self.width as f64) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x] * self.intensity
+    }
+
+    /// Samples a direction towards the environment, returns it with its solid angle density.
+    pub fn sample_direction(&self, u: Vector2<f64>) -> (Vector3<f64>, f64) {
+        let ((x, y), pdf) = self.distribution.sample_at(u.x, u.y);
+        let (direction, sin_theta) = Self::direction(x, y);
+        if pdf == 0. || sin_theta == 0. {
+            return (direction, 0.);
+        }
+        (self.rotation * direction, pdf / (2. * PI * PI * sin_theta))
+    }
+
+    /// Solid angle density with which [`EnvironmentLight::sample_direction`] picks `direction`.
+    pub fn direction_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        let local = self.rotation.inverse() * direction;
+        let sin_theta = (1. - local.y * local.y).max(0.).sqrt();
+        if sin_theta == 0. {
+            return 0.;
+        }
+        let uv = Self::equirectangular(&local);
+        self.distribution.pdf(uv.x, uv.y) / (2. * PI * PI * sin_theta)
+    }
+
+    /// The power falling onto a scene bounded by a sphere of radius `scene_radius`.
+    pub fn power(&self, scene_radius: f64) -> f64 {
+        let weights = (0..self.height).flat_map(|y| {
+            let theta = (y as f64 + 0.5) / self.height as f64 * PI;
+            (0..self.width).map(move |_| theta.sin())
+        });
+        let (total, total_weight) = self.pixels.iter().zip(weights).fold(
+            (0., 0.),
+            |(total, total_weight), (pixel, weight)| {
+                (total + luminance(pixel) * weight, total_weight + weight)
+            },
+        );
+
+        let average = total / total_weight * self.intensity;
+        PI * scene_radius * scene_radius * PI * average
+    }
+
+    /// Image coordinates in [0, 1) of a direction, with v pointing down.
+    fn equirectangular(direction: &Vector3<f64>) -> Vector2<f64> {
+        let theta = direction.y.clamp(-1., 1.).acos();
+        let phi = direction.x.atan2(-direction.z);
+        Vector2::new(
+            (phi / TAU + 0.5).rem_euclid(1.),
+            (theta / PI).clamp(0., 1. - f64::EPSILON),
+        )
+    }
+
+    /// The direction at image coordinates and the sine of its angle to the vertical.
+    fn direction(u: f64, v: f64) -> (Vector3<f64>, f64) {
+        let theta = v * PI;
+        let phi = (u - 0.5) * TAU;
+        let sin_theta = theta.sin();
+        (
+            Vector3::new(sin_theta * phi.sin(), theta.cos(), -sin_theta * phi.cos()),
+            sin_theta,
+        )
+    }
+}
@@ -0,0 +1,234 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use nalgebra as na;
+
+use na::{Matrix3, Vector3};
+
+use super::{DirectionalLight, EnvironmentLight, Light};
+
+/// Angular diameter of the sun as seen from the earth, in radians.
+const SUN_ANGULAR_DIAMETER: f64 = 0.0093;
+
+/// Resolution of the environment map that [`PreethamSky::lights`] bakes the sky into.
+const SKY_WIDTH: usize = 512;
+const SKY_HEIGHT: usize = 256;
+
+/// Illuminance of the sun at the top of the atmosphere relative to the zenith luminance units of
+/// the sky, in kilolux.
+const SUN_ILLUMINANCE: f64 = 128.;
+
+/// Linear sRGB from CIE XYZ.
+#[rustfmt::skip]
+const XYZ_TO_RGB: Matrix3<f64> = Matrix3::new(
+    3.2406, -1.5372, -0.4986,
+    -0.9689, 1.8758, 0.0415,
+    0.0557, -0.2040, 1.0570,
+);
+
+/// Coefficients of the Perez sky luminance distribution.
+#[derive(Debug, Clone, Copy)]
+struct Perez([f64; 5]);
+
+impl Perez {
+    /// Relative luminance at an angle `theta` from the zenith and `gamma` from the sun.
+    fn evaluate(&self, theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+        (1. + a * (b / theta.cos().max(0.01)).exp())
+            * (1. + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// The analytic daylight model of Preetham, Shirley and Smits, "A Practical Analytic Model for
+/// Daylight", for a clear sky. The sky is set by the position of the sun and the turbidity, the
+/// haziness of the atmosphere from about 2 for a very clear sky to 10 for a hazy one. Radiance
+/// is in kilocandela per square meter scaled by an intensity, which defaults to 0.05 to keep
+/// midday values around 1.
+///
+/// Directions use the same frame as [`EnvironmentLight`]: +y is up and an azimuth of 0 faces
+/// along -z, turning towards +x.
+#[derive(Debug, Clone)]
+pub struct PreethamSky {
+    /// Direction towards the sun.
+    sun_direction: Vector3<f64>,
+    turbidity: f64,
+    intensity: f64,
+    /// Perez coefficients for the luminance Y and the chromaticities x and y.
+    perez: [Perez; 3],
+    /// Y, x and y at the zenith.
+    zenith: [f64; 3],
+}
+
+impl PreethamSky {
+    /// `elevation` is the angle of the sun above the horizon and `azimuth` its angle around the
+    /// vertical, both in radians. The turbidity is clamped to the range the model was fitted
+    /// for.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let t = turbidity.clamp(1.7, 10.);
+        let sun_direction = Vector3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+
+        let perez = [
+            Perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]),
+            Perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]),
+            Perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]),
+        ];
+
+        // The zenith values are only fitted for the sun above the horizon.
+        let theta_sun = (FRAC_PI_2 - elevation).clamp(0., FRAC_PI_2);
+        let chi = (4. / 9. - t / 120.) * (PI - 2. * theta_sun);
+        let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.);
+
+        let cubic = |c: [f64; 4]| {
+            c[0] * theta_sun.powi(3) + c[1] * theta_sun.powi(2) + c[2] * theta_sun + c[3]
+        };
+        let chromaticity = |c: [[f64; 4]; 3]| t * t * cubic(c[0]) + t * cubic(c[1]) + cubic(c[2]);
+        let x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        Self {
+            sun_direction,
+            turbidity: t,
+            intensity: 0.05,
+            perez,
+            zenith: [luminance, x, y],
+        }
+    }
+
+    /// Scales the radiance of the sky and the sun.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn sun_direction(&self) -> Vector3<f64> {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Radiance of the sky arriving from `direction`, without the sun itself. The model doesn't
+    /// cover the ground, which is black.
+    pub fn radiance(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        let direction = direction.normalize();
+        if direction.y <= 0. {
+            return Vector3::zeros();
+        }
+
+        let theta = direction.y.acos();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1., 1.).acos();
+        let theta_sun = self.sun_direction.y.clamp(-1., 1.).acos().min(FRAC_PI_2);
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let perez = &self.perez[i];
+            self.zenith[i] * perez.evaluate(theta, gamma) / perez.evaluate(0., theta_sun)
+        });
+        xyy_to_rgb(x, y, luminance) * self.intensity
+    }
+
+    /// Light from the sun after passing through the atmosphere, as irradiance on a surface
+    /// facing it. Rayleigh scattering and the haze take away more blue as the sun sets.
+    pub fn sun_irradiance(&self) -> Vector3<f64> {
+        let elevation = self.sun_direction.y.clamp(-1., 1.).asin();
+        if elevation <= 0. {
+            return Vector3::zeros();
+        }
+
+        // Relative optical air mass after Kasten and Young.
+        let zenith_degrees = 90. - elevation.to_degrees();
+        let air_mass = 1. / (elevation.sin() + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+
+        // Optical depths at wavelengths for red, green and blue, in micrometers, with the
+        // Ångström turbidity estimated from the Linke turbidity.
+        let beta = (0.04608 * self.turbidity - 0.04586).max(0.);
+        let transmittance = Vector3::new(0.68, 0.55, 0.44).map(|wavelength: f64| {
+            let rayleigh = 0.008735 * wavelength.powf(-4.08);
+            let aerosol = beta * wavelength.powf(-1.3);
+            (-(rayleigh + aerosol) * air_mass).exp()
+        });
+
+        transmittance * SUN_ILLUMINANCE * self.intensity
+    }
+
+    /// The sky baked into an environment light of the given resolution, which lets it be
+    /// importance sampled.
+    pub fn environment(&self, width: usize, height: usize) -> EnvironmentLight {
+        let pixels = (0..width * height)
+            .map(|i| {
+                let theta = ((i / width) as f64 + 0.5) / height as f64 * PI;
+                let phi = (((i % width) as f64 + 0.5) / width as f64 - 0.5) * TAU;
+                self.radiance(&Vector3::new(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    -theta.sin() * phi.cos(),
+                ))
+            })
+            .collect();
+        EnvironmentLight::new(width, height, pixels)
+    }
+
+    /// A directional light for the sun disk that matches the sky.
+    pub fn sun(&self) -> DirectionalLight {
+        let irradiance = self.sun_irradiance();
+        let strength = irradiance.max();
+        let color = if strength > 0. {
+            irradiance / strength
+        } else {
+            Vector3::zeros()
+        };
+        DirectionalLight::new(-self.sun_direction, color, strength, SUN_ANGULAR_DIAMETER)
+    }
+
+    /// The sky as the scene background and the sun, ready to be added to a
+    /// [`Scene`](crate::Scene). The sun is left out once it has set.
+    pub fn lights(&self) -> Vec<Light> {
+        let mut lights = vec![self.environment(SKY_WIDTH, SKY_HEIGHT).into()];
+        if self.sun_direction.y > 0. {
+            lights.push(self.sun().into());
+        }
+        lights
+    }
+}
+
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Vector3<f64> {
+    if y <= 0. {
+        return Vector3::zeros();
+    }
+    let xyz = Vector3::new(x * luminance / y, luminance, (1. - x - y) * luminance / y);
+    (XYZ_TO_RGB * xyz).map(|c| c.max(0.))
+}
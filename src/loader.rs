@@ -0,0 +1,513 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use nalgebra::{Point3, Vector3};
+
+use crate::{object::ObjectDefinition, shape::TriangleMesh, Material};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "Could not read file: {error}"),
+            LoadError::Parse { line, message } => write!(f, "Line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> LoadError {
+    LoadError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_floats<const N: usize>(
+    line: usize,
+    arguments: &[&str],
+    defaults: [f64; N],
+) -> Result<[f64; N], LoadError> {
+    let mut result = defaults;
+    for (i, argument) in arguments.iter().take(N).enumerate() {
+        result[i] = argument
+            .parse()
+            .map_err(|_| parse_error(line, format!("Invalid number \"{argument}\"")))?;
+    }
+    Ok(result)
+}
+
+/// Resolves a 1-based (or negative, relative to the end) OBJ index into a 0-based index.
+fn resolve_index(line: usize, index: &str, count: usize) -> Result<usize, LoadError> {
+    let index: i64 = index
+        .parse()
+        .map_err(|_| parse_error(line, format!("Invalid index \"{index}\"")))?;
+
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        Err(parse_error(line, format!("Index {index} is out of bounds")))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MaterialDescription {
+    diffuse: Vector3<f64>,
+    emission: Vector3<f64>,
+    specular_exponent: Option<f64>,
+    ior: f64,
+    dissolve: f64,
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        Self {
+            diffuse: Vector3::new(0.8, 0.8, 0.8),
+            emission: Vector3::zeros(),
+            specular_exponent: None,
+            ior: 1.,
+            dissolve: 1.,
+        }
+    }
+}
+
+impl MaterialDescription {
+    /// The usual Blinn-Phong exponent to microfacet roughness conversion, fully rough without an
+    /// exponent.
+    fn roughness(&self) -> f64 {
+        self.specular_exponent
+            .map(|exponent| (2. / (exponent.max(0.) + 2.)).sqrt())
+            .unwrap_or(1.)
+    }
+
+    fn transmission(&self) -> f64 {
+        (1. - self.dissolve).clamp(0., 1.)
+    }
+
+    fn to_material(&self) -> Material {
+        let material = Material::new_reflective(
+            self.diffuse,
+            self.roughness(),
+            self.transmission(),
+            self.ior,
+        );
+
+        if self.emission.max() > 0. {
+            material.with_emission(self.emission, 1.)
+        } else {
+            material
+        }
+    }
+}
+
+fn parse_mtl(source: &str) -> Result<HashMap<String, MaterialDescription>, LoadError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MaterialDescription)> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, description)) = current.take() {
+                materials.insert(name, description);
+            }
+            current = Some((arguments.join(" "), MaterialDescription::default()));
+            continue;
+        }
+
+        let Some((_, description)) = &mut current else {
+            continue;
+        };
+
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats(line_number, &arguments, [0.; 3])?;
+                description.diffuse = Vector3::new(r, g, b);
+            }
+            "Ke" => {
+                let [r, g, b] = parse_floats(line_number, &arguments, [0.; 3])?;
+                description.emission = Vector3::new(r, g, b);
+            }
+            "Ns" => {
+                let [exponent] = parse_floats(line_number, &arguments, [0.])?;
+                description.specular_exponent = Some(exponent);
+            }
+            "Ni" => {
+                let [ior] = parse_floats(line_number, &arguments, [1.])?;
+                description.ior = ior;
+            }
+            "d" => {
+                let [dissolve] = parse_floats(line_number, &arguments, [1.])?;
+                description.dissolve = dissolve;
+            }
+            "Tr" => {
+                let [transparency] = parse_floats(line_number, &arguments, [0.])?;
+                description.dissolve = 1. - transparency;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, description)) = current {
+        materials.insert(name, description);
+    }
+
+    Ok(materials)
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    vertices: Vec<Point3<f64>>,
+    normals: Vec<Vector3<f64>>,
+    indices: Vec<[usize; 3]>,
+    vertex_lookup: HashMap<(usize, Option<usize>), usize>,
+    has_all_normals: bool,
+}
+
+impl MeshBuilder {
+    fn new() -> Self {
+        Self {
+            has_all_normals: true,
+            ..Default::default()
+        }
+    }
+
+    fn add_vertex(
+        &mut self,
+        key: (usize, Option<usize>),
+        positions: &[Point3<f64>],
+        normals: &[Vector3<f64>],
+    ) -> usize {
+        if let Some(&index) = self.vertex_lookup.get(&key) {
+            return index;
+        }
+
+        let (position_index, normal_index) = key;
+        self.vertices.push(positions[position_index]);
+        match normal_index {
+            Some(normal_index) => self.normals.push(normals[normal_index].normalize()),
+            None => {
+                self.has_all_normals = false;
+                self.normals.push(Vector3::zeros());
+            }
+        }
+
+        let index = self.vertices.len() - 1;
+        self.vertex_lookup.insert(key, index);
+        index
+    }
+
+    fn build(self) -> TriangleMesh {
+        if self.has_all_normals {
+            TriangleMesh::with_normals(self.vertices, self.normals, self.indices)
+        } else {
+            TriangleMesh::new(self.vertices, self.indices)
+        }
+    }
+}
+
+/// Parses the contents of an OBJ file, `materials` holds the contents of the referenced MTL files.
+fn parse_obj(
+    source: &str,
+    materials: &HashMap<String, MaterialDescription>,
+) -> Result<Vec<ObjectDefinition>, LoadError> {
+    let mut positions = vec![];
+    let mut normals = vec![];
+
+    let mut group = String::new();
+    let mut material_name = String::new();
+
+    // Keep the order in which groups were first encountered so the output is deterministic.
+    let mut group_order: Vec<(String, String)> = vec![];
+    let mut meshes: HashMap<(String, String), MeshBuilder> = HashMap::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z, w] = parse_floats(line_number, &arguments, [0., 0., 0., 1.])?;
+                if w == 0. {
+                    return Err(parse_error(
+                        line_number,
+                        "A vertex position needs a nonzero w",
+                    ));
+                }
+                positions.push(Point3::new(x / w, y / w, z / w));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(line_number, &arguments, [0.; 3])?;
+                normals.push(Vector3::new(x, y, z));
+            }
+            "o" | "g" => {
+                group = arguments.join(" ");
+            }
+            "usemtl" => {
+                material_name = arguments.join(" ");
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(parse_error(line_number, "A face needs at least 3 vertices"));
+                }
+
+                let key = (group.clone(), material_name.clone());
+                let mesh = meshes.entry(key.clone()).or_insert_with(|| {
+                    group_order.push(key);
+                    MeshBuilder::new()
+                });
+
+                let mut face = Vec::with_capacity(arguments.len());
+                for argument in &arguments {
+                    let mut parts = argument.split('/');
+                    let position_index =
+                        resolve_index(line_number, parts.next().unwrap(), positions.len())?;
+                    let _texture_index = parts.next();
+                    let normal_index = match parts.next() {
+                        Some(index) if !index.is_empty() => {
+                            Some(resolve_index(line_number, index, normals.len())?)
+                        }
+                        _ => None,
+                    };
+
+                    face.push(mesh.add_vertex(
+                        (position_index, normal_index),
+                        &positions,
+                        &normals,
+                    ));
+                }
+
+                // Polygons are triangulated as a fan around their first vertex.
+                for i in 1..face.len() - 1 {
+                    mesh.indices.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(group_order
+        .into_iter()
+        .map(|key| {
+            let material = materials
+                .get(&key.1)
+                .cloned()
+                .unwrap_or_default()
+                .to_material();
+            let mesh = meshes.remove(&key).unwrap().build();
+
+            ObjectDefinition {
+                shape: Box::new(mesh),
+                material,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+/// Loads a Wavefront OBJ file into one object per group and material.
+/// Material libraries referenced with `mtllib`, which can list several, are resolved relative to
+/// the OBJ file. Exports often reference libraries that weren't shipped along, so libraries that
+/// can't be read are skipped and their materials get the default material.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Vec<ObjectDefinition>, LoadError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = HashMap::new();
+    for line in source.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("mtllib") {
+            continue;
+        }
+        for library in tokens {
+            let Ok(mtl_source) = fs::read_to_string(directory.join(library)) else {
+                continue;
+            };
+            materials.extend(parse_mtl(&mtl_source)?);
+        }
+    }
+
+    parse_obj(&source, &materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector2;
+
+    use crate::{Ray, ShadingPoint};
+
+    fn parse(source: &str) -> Vec<ObjectDefinition> {
+        parse_obj(source, &HashMap::new()).unwrap()
+    }
+
+    fn normal_at_center(object: &ObjectDefinition) -> Vector3<f64> {
+        let ray = Ray {
+            origin: Point3::new(0.25, 0.25, 1.),
+            direction: -Vector3::z(),
+        };
+        object.shape.intersection(&ray).unwrap().normal
+    }
+
+    const SQUARE: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+";
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let objects = parse(&format!("{SQUARE}f -4 -3 -2"));
+        assert_eq!(objects.len(), 1);
+        assert!((objects[0].shape.area() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn out_of_range_indices_fail() {
+        for face in ["f 1 2 5", "f 0 1 2", "f -5 1 2", "f 1//2 2//2 3//2"] {
+            match parse_obj(&format!("{SQUARE}{face}"), &HashMap::new()).err() {
+                Some(LoadError::Parse { line: 6, .. }) => {}
+                error => panic!("{face} gave {error:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn face_normals_are_used() {
+        let tilted = Vector3::new(1., 0., 1.).normalize();
+        for face in ["f 1//1 2//1 3//1", "f 1/1/1 2/2/1 3/3/1"] {
+            let objects = parse(&format!("{SQUARE}vn 1 0 1\n{face}"));
+            assert!((normal_at_center(&objects[0]) - tilted).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let objects = parse(&format!("{SQUARE}f 1 2 3 4"));
+        assert!((objects[0].shape.area() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn faces_are_grouped_by_group_and_material() {
+        let source = format!(
+            "{SQUARE}
+o first
+usemtl red
+f 1 2 3
+usemtl blue
+f 1 3 4
+g second
+f 1 2 4
+o first
+usemtl red
+f 2 3 4
+"
+        );
+        let objects = parse(&source);
+        let areas: Vec<f64> = objects.iter().map(|object| object.shape.area()).collect();
+        assert_eq!(areas, vec![1., 0.5, 0.5]);
+    }
+
+    #[test]
+    fn positions_are_divided_by_w() {
+        let objects = parse("v 2 4 6 2\nv 0 0 0\nv 1 0 0 0.5\nf 1 2 3");
+        let bounds = objects[0].shape.bounding_box();
+        assert_eq!(bounds.min, Point3::new(0., 0., 0.));
+        assert_eq!(bounds.max, Point3::new(2., 2., 3.));
+        assert!(parse_obj("v 1 2 3 0", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn materials_are_converted() {
+        let materials = parse_mtl(
+            "
+newmtl shiny
+Ns 98
+d 0.25
+newmtl clear glass
+Tr 0.75
+newmtl plain
+",
+        )
+        .unwrap();
+
+        let shiny = &materials["shiny"];
+        assert!((shiny.roughness() - 0.02_f64.sqrt()).abs() < 1e-9);
+        assert!((shiny.transmission() - 0.75).abs() < 1e-9);
+
+        let glass = &materials["clear glass"];
+        assert_eq!(glass.roughness(), 1.);
+        assert!((glass.transmission() - 0.75).abs() < 1e-9);
+
+        assert_eq!(materials["plain"].transmission(), 0.);
+    }
+
+    #[test]
+    fn transparent_materials_refract() {
+        let materials = parse_mtl("newmtl glass\nNs 100000\nNi 1.5\nd 0").unwrap();
+        let material = materials["glass"].to_material();
+
+        let wo = Vector3::new(1., 0., 1.).normalize();
+        let point = ShadingPoint {
+            position: Point3::origin(),
+            normal: Vector3::z(),
+            uv: Vector2::zeros(),
+        };
+        let sample = material
+            .bsdf()
+            .unwrap()
+            .sample(&wo, &point, 0.999, Vector2::new(0.5, 0.5))
+            .unwrap();
+
+        assert!(sample.wi.z < 0.);
+        assert!((sample.wi.x + wo.x / 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn missing_material_libraries_are_skipped() {
+        let path = std::env::temp_dir().join("loader_missing_mtllib.obj");
+        fs::write(
+            &path,
+            format!("mtllib missing.mtl\n{SQUARE}usemtl red\nf 1 2 3"),
+        )
+        .unwrap();
+        let objects = load_obj(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(objects.unwrap().len(), 1);
+    }
+}
@@ -1,45 +1,20 @@
-use std::{f64::consts::PI, sync::Arc};
+use std::sync::Arc;
 
 use na::{Point3, Vector3};
 use nalgebra as na;
-use rand::{thread_rng, Rng};
-use rand_distr::StandardNormal;
 
 use crate::{
-    find_normal, function_approximation::ProbabilityDensityFunction, reflect,
-    shape::IntersectionInfo, Ray, Shader,
+    bsdf::{Dielectric, Glossy, Lambertian, ShadingFrame},
+    shape::IntersectionInfo,
+    Bsdf, Emission, Ray, Sampler, Shader, ShadingPoint,
 };
 
-#[derive(Clone)]
-pub enum Material {
-    Reflective {
-        color: Arc<dyn Shader>,
-        roughness: f64,
-        transmission: f64,
-        ior: f64,
-        pdf: ProbabilityDensityFunction,
-    },
-    Emissive {
-        color: Vector3<f64>,
-    },
-}
-
-// fn ggx(x: f64, roughness: f64) -> f64 {
-//     let roughness = roughness.powf(2.) + 0.0001;
-//     let x = 1. - x;
-//     E.powf(-(x * x) / (roughness * roughness) / 2.) / (roughness * TAU.sqrt())
-// }
-
-fn ggx(x: f64, roughness: f64) -> f64 {
-    if roughness < 0.001 {
-        if x >= 0.9999 {
-            1.
-        } else {
-            0.
-        }
-    } else {
-        roughness.powi(2) / (PI * (x.powi(2) * (roughness.powi(2) - 1.) + 1.).powi(2))
-    }
+/// The surface properties of an object: an optional BSDF that scatters light and an optional
+/// emission. A material with neither is a perfect absorber.
+#[derive(Clone, Default)]
+pub struct Material {
+    bsdf: Option<Arc<dyn Bsdf>>,
+    emission: Option<Emission>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -52,165 +27,236 @@ pub struct SurfaceInteraction {
     pub pdf: f64,
 }
 
+/// A sampled scattering direction, as used by renderers that need exact densities.
+#[derive(Clone, Copy, Debug)]
+pub struct ScatteringSample {
+    pub direction: Vector3<f64>,
+    /// The BSDF value times the cosine term, divided by the pdf.
+    pub weight: Vector3<f64>,
+    /// Solid angle density of `direction`, or the probability of the chosen lobe when
+    /// `specular` is set.
+    pub pdf: f64,
+    /// Set when the direction can not be evaluated through [`Material::evaluate`], light
+    /// connections can not reach it.
+    pub specular: bool,
+    /// The relative index of refraction crossed by a transmitted sample, 1 for reflections.
+    pub eta: f64,
+}
+
 impl Material {
+    /// A glossy reflector of the given `color`. With any `transmission` it becomes a
+    /// [`Dielectric`] with index of refraction `ior` and the same `roughness` instead, where the
+    /// Fresnel equations decide how much light passes and `color` and the amount of
+    /// `transmission` are ignored.
     pub fn new_reflective<S: Shader + 'static>(
         color: S,
         roughness: f64,
         transmission: f64,
         ior: f64,
     ) -> Self {
-        let pdf = ProbabilityDensityFunction::build(|x| ggx(x, roughness), 1000);
-        Self::Reflective {
-            color: Arc::new(color),
-            roughness,
-            transmission,
-            ior,
-            pdf,
+        if transmission > 0. {
+            Self::new_bsdf(Dielectric::new(ior, roughness))
+        } else {
+            Self::new_bsdf(Glossy::new(color, roughness, 0., ior))
         }
     }
 
-    pub fn new_emissive(color: Vector3<f64>) -> Self {
-        Self::Emissive { color }
+    pub fn new_lambertian<S: Shader + 'static>(albedo: S) -> Self {
+        Self::new_bsdf(Lambertian::new(albedo))
+    }
+
+    pub fn new_bsdf<B: Bsdf + 'static>(bsdf: B) -> Self {
+        Self {
+            bsdf: Some(Arc::new(bsdf)),
+            emission: None,
+        }
+    }
+
+    /// A one-sided light source that doesn't reflect anything.
+    pub fn new_emissive<S: Shader + 'static>(color: S, strength: f64) -> Self {
+        Self::new_emitter(Emission::new(color, strength))
+    }
+
+    /// A light source that doesn't reflect anything, for emission with more settings.
+    pub fn new_emitter(emission: Emission) -> Self {
+        Self {
+            bsdf: None,
+            emission: Some(emission),
+        }
     }
 
     pub fn new(color: Vector3<f64>, roughness: f64, emissive: bool) -> Self {
         if emissive {
-            Self::new_emissive(color)
+            Self::new_emissive(color, 1.)
         } else {
             Self::new_reflective(color, roughness, 0., 1.)
         }
     }
 
-    pub fn emission_color(&self) -> Vector3<f64> {
-        match self {
-            Material::Emissive { color } => *color,
-            _ => Vector3::zeros(),
+    /// Adds emission on top of the scattering of this material, for example a glowing screen
+    /// behind glass.
+    pub fn with_emission<S: Shader + 'static>(self, color: S, strength: f64) -> Self {
+        self.with_emitter(Emission::new(color, strength))
+    }
+
+    pub fn with_emitter(mut self, emission: Emission) -> Self {
+        self.emission = Some(emission);
+        self
+    }
+
+    pub fn bsdf(&self) -> Option<&dyn Bsdf> {
+        self.bsdf.as_deref()
+    }
+
+    pub fn emitter(&self) -> Option<&Emission> {
+        self.emission.as_ref()
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emission.is_some()
+    }
+
+    /// Radiance emitted at `point` towards `direction`.
+    pub fn emission(&self, point: &ShadingPoint, direction: &Vector3<f64>) -> Vector3<f64> {
+        match &self.emission {
+            Some(emission) => emission.radiance(point, direction),
+            None => Vector3::zeros(),
+        }
+    }
+
+    /// Whether all scattering of this material is perfectly specular, which can't be evaluated
+    /// for arbitrary directions.
+    pub fn is_specular(&self) -> bool {
+        self.bsdf().is_some_and(|bsdf| bsdf.is_specular())
+    }
+
+    /// Samples an outgoing direction through the BSDF of this material.
+    pub fn sample_scattering(
+        &self,
+        incoming: &Vector3<f64>,
+        point: &ShadingPoint,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteringSample> {
+        let bsdf = self.bsdf()?;
+        let frame = ShadingFrame::new(&point.normal);
+
+        let uc = sampler.get_1d();
+        let u = sampler.get_2d();
+        let sample = bsdf.sample(&frame.to_local(&-incoming), point, uc, u)?;
+
+        if sample.pdf > 0. {
+            Some(ScatteringSample {
+                direction: frame.to_world(&sample.wi),
+                weight: sample.f * sample.wi.z.abs() / sample.pdf,
+                pdf: sample.pdf,
+                specular: sample.specular,
+                eta: sample.eta,
+            })
+        } else {
+            None
         }
     }
 
-    pub fn absorption_color(&self, local_position: &Vector3<f64>) -> Vector3<f64> {
-        match self {
-            Material::Reflective { color, .. } => color.shade(local_position),
-            _ => Vector3::new(1., 1., 1.),
+    /// The BSDF for light arriving from `outgoing` and leaving towards `-incoming`. Specular
+    /// scattering is not included.
+    pub fn evaluate(
+        &self,
+        incoming: &Vector3<f64>,
+        outgoing: &Vector3<f64>,
+        point: &ShadingPoint,
+    ) -> Vector3<f64> {
+        match self.bsdf() {
+            Some(bsdf) => {
+                let frame = ShadingFrame::new(&point.normal);
+                bsdf.eval(
+                    &frame.to_local(&-incoming),
+                    &frame.to_local(outgoing),
+                    point,
+                )
+            }
+            None => Vector3::zeros(),
         }
     }
 
-    pub fn likelihood(
+    /// Solid angle density with which [`Material::sample_scattering`] picks `outgoing` through
+    /// non-specular scattering.
+    pub fn scattering_pdf(
         &self,
         incoming: &Vector3<f64>,
         outgoing: &Vector3<f64>,
         normal: &Vector3<f64>,
     ) -> f64 {
-        match self {
-            Material::Reflective {
-                pdf,
-                transmission,
-                ior,
-                ..
-            } => {
-                if (normal.dot(incoming) < 0.) == (normal.dot(outgoing) > 0.) {
-                    let mut sampled_normal = find_normal(incoming, outgoing);
-                    if sampled_normal.dot(normal) < 0. {
-                        sampled_normal *= -1.;
-                    }
-                    let angle_dot = sampled_normal.dot(normal);
-                    pdf.likelihood(angle_dot) * (1. - transmission)
-                } else {
-                    let direction = if normal.dot(incoming) >= 0. {
-                        normal.slerp(incoming, *ior)
-                    } else {
-                        (-normal).slerp(incoming, 1. / ior)
-                    };
-
-                    let angle_dot = direction.dot(outgoing);
-                    pdf.likelihood(angle_dot) * transmission
-                }
+        match self.bsdf() {
+            Some(bsdf) => {
+                let frame = ShadingFrame::new(normal);
+                bsdf.pdf(&frame.to_local(&-incoming), &frame.to_local(outgoing))
             }
-            Material::Emissive { .. } => outgoing.dot(normal).max(0.),
+            None => 0.,
         }
     }
 
-    pub fn interact(&self, incoming: &Ray, intersection: &IntersectionInfo) -> SurfaceInteraction {
-        let mut intersection = *intersection;
-
-        // if intersection.normal.dot(&incoming.direction) > 0. {
-        //     intersection.normal = -intersection.normal;
-        // }
-
-        match self {
-            Material::Reflective {
-                color,
-                roughness,
-                pdf,
-                transmission,
-                ior,
-                ..
-            } => {
-                let mut rng = thread_rng();
-                let transmitted = rng.gen_bool(*transmission);
-
-                let (desired_angle, likelihood) = if *roughness == 0. {
-                    (1., 1.)
-                } else {
-                    let sample = pdf.sample(&mut rng);
-                    let likelihood = pdf.likelihood(sample);
-                    (sample, likelihood)
-                };
-                if intersection.normal.dot(&incoming.direction) > 0. {
-                    intersection.normal = -intersection.normal;
-                }
-
-                let specular_normal = &intersection.normal;
-                let random_direction: Vector3<f64> =
-                    Vector3::from_distribution(&StandardNormal, &mut rng).normalize();
-
-                let perpendicular_vector = specular_normal.cross(&random_direction);
-
-                let scatter_normal = perpendicular_vector.slerp(specular_normal, desired_angle);
-
-                // if scatter_normal.dot(&intersection.normal) < 0. {
-                //     scatter_normal = -scatter_normal;
-                // }
-
-                let (outgoing_direction, likelihood) = if transmitted {
-                    if scatter_normal.dot(&incoming.direction) >= 0. {
-                        let direction = scatter_normal.slerp(&incoming.direction, *ior);
-                        (direction, likelihood * transmission)
-                    } else {
-                        let direction = (-scatter_normal).slerp(&incoming.direction, 1. / ior);
-                        (direction, likelihood * transmission)
-                    }
-                } else {
-                    let mut outgoing_direction = reflect(&incoming.direction, &scatter_normal);
-
-                    if outgoing_direction.dot(specular_normal) < 0. {
-                        outgoing_direction = reflect(&outgoing_direction, specular_normal);
-                    }
-
-                    (outgoing_direction, likelihood * (1.0 - transmission))
-                };
-
-                let outgoing = Ray {
-                    direction: outgoing_direction,
-                    origin: intersection.position + outgoing_direction * 0.001,
-                };
-
-                SurfaceInteraction {
-                    position: intersection.position,
-                    surface_normal: intersection.normal,
-                    filter: color.shade(&intersection.position.coords),
-                    emission: Vector3::zeros(),
-                    outgoing: Some(outgoing),
-                    pdf: likelihood,
-                }
-            }
-            Material::Emissive { color } => SurfaceInteraction {
+    /// A relative likelihood between 0 and 1 of scattering `incoming` into `outgoing`.
+    pub fn likelihood(
+        &self,
+        incoming: &Vector3<f64>,
+        outgoing: &Vector3<f64>,
+        normal: &Vector3<f64>,
+    ) -> f64 {
+        if self.bsdf.is_some() {
+            self.scattering_pdf(incoming, outgoing, normal).min(1.)
+        } else if self.is_emissive() {
+            outgoing.dot(normal).max(0.)
+        } else {
+            0.
+        }
+    }
+
+    pub fn interact(
+        &self,
+        incoming: &Ray,
+        intersection: &IntersectionInfo,
+        sampler: &mut dyn Sampler,
+    ) -> SurfaceInteraction {
+        let point = ShadingPoint::from(intersection);
+        let emission = self.emission(&point, &-incoming.direction);
+
+        if self.bsdf.is_none() {
+            return SurfaceInteraction {
                 position: intersection.position,
                 surface_normal: intersection.normal,
                 filter: Vector3::new(1., 1., 1.),
-                emission: *color,
+                emission,
                 outgoing: None,
                 pdf: 1.,
+            };
+        }
+
+        let sample = self.sample_scattering(&incoming.direction, &point, sampler);
+        let surface_normal = if intersection.normal.dot(&incoming.direction) > 0. {
+            -intersection.normal
+        } else {
+            intersection.normal
+        };
+
+        match sample {
+            Some(sample) => SurfaceInteraction {
+                position: intersection.position,
+                surface_normal,
+                filter: sample.weight,
+                emission,
+                outgoing: Some(Ray {
+                    direction: sample.direction,
+                    origin: intersection.position + sample.direction * 0.001,
+                }),
+                pdf: sample.pdf.min(1.),
+            },
+            None => SurfaceInteraction {
+                position: intersection.position,
+                surface_normal,
+                filter: Vector3::zeros(),
+                emission,
+                outgoing: None,
+                pdf: 0.,
             },
         }
     }
@@ -1,10 +1,9 @@
 use nalgebra::{Similarity3, Vector3};
-use rand::thread_rng;
-use rand_distr::StandardNormal;
 
 use crate::{
+    bsdf::{cosine_sample_hemisphere, ShadingFrame},
     shape::{Empty, IntersectionInfo},
-    Material, Ray, Shape,
+    BoundingBox, Material, Ray, Sampler, ShadingPoint, Shape,
 };
 
 pub struct ObjectDefinition {
@@ -23,9 +22,7 @@ impl Default for ObjectDefinition {
     fn default() -> Self {
         Self {
             shape: Box::new(Empty),
-            material: Material::Emissive {
-                color: Vector3::zeros(),
-            },
+            material: Material::default(),
             x: 0.,
             y: 0.,
             z: 0.,
@@ -42,6 +39,7 @@ pub struct Object {
     transform: Similarity3<f64>,
     inverse_transform: Similarity3<f64>,
     material: Material,
+    pub(crate) light_probability: f64,
 }
 
 impl Object {
@@ -56,6 +54,7 @@ impl Object {
             transform,
             inverse_transform: transform.inverse(),
             material: definition.material,
+            light_probability: 0.,
         }
     }
 
@@ -67,25 +66,41 @@ impl Object {
         &self.material
     }
 
-    pub fn sample_emissive_ray(&self) -> Ray {
-        let origin = self.shape.sample_random_point();
-        let normal = self.shape.sample_normal(origin);
+    /// The probability that [`Scene::sample_light`](crate::Scene::sample_light) picks this
+    /// object, zero unless it is emissive.
+    pub fn light_probability(&self) -> f64 {
+        self.light_probability
+    }
 
-        let mut direction =
-            Vector3::from_distribution(&StandardNormal, &mut thread_rng()).normalize();
+    /// Samples a point on the surface and a ray leaving it, distributed as described by
+    /// [`Emission::sample_direction`](crate::Emission::sample_direction).
+    pub fn sample_emissive_ray(&self, sampler: &mut dyn Sampler) -> (ShadingPoint, Ray) {
+        let point = self.sample_surface_point(sampler);
 
-        if direction.dot(&normal) < 0. {
-            direction = -direction;
-        }
+        let u = sampler.get_2d();
+        let direction = match self.material.emitter() {
+            Some(emission) => emission.sample_direction(&point.normal, u),
+            None => ShadingFrame::new(&point.normal).to_world(&cosine_sample_hemisphere(u)),
+        };
 
-        let local_ray = Ray { origin, direction };
+        let ray = Ray {
+            origin: point.position + direction * 0.001,
+            direction,
+        };
 
-        let mut global_ray = local_ray.transform_similarity(&self.transform);
-        global_ray.direction.normalize_mut();
+        (point, ray)
+    }
 
-        global_ray.origin += global_ray.direction * 0.001;
+    /// Samples a point uniformly over the surface, returns it in world space along with its
+    /// texture coordinates.
+    pub fn sample_surface_point(&self, sampler: &mut dyn Sampler) -> ShadingPoint {
+        let point = self.shape.sample_surface_point(sampler.get_2d());
 
-        global_ray
+        ShadingPoint {
+            position: self.transform.transform_point(&point.position),
+            normal: self.transform.isometry.rotation * point.normal,
+            uv: point.uv,
+        }
     }
 
     pub fn local_intersection(&self, ray: &Ray) -> Option<IntersectionInfo> {
@@ -94,6 +109,21 @@ impl Object {
         self.shape.intersection(&local_ray)
     }
 
+    /// Whether the object blocks the ray before it reaches `max_distance`, the ray is given in
+    /// world space.
+    pub fn blocks(&self, ray: &Ray, max_distance: f64) -> bool {
+        let local_ray = ray.transform_similarity(&self.inverse_transform);
+
+        self.shape.blocks(&local_ray, max_distance)
+    }
+
+    /// The world space bounding box of the transformed shape.
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.shape
+            .bounding_box()
+            .transform_similarity(&self.transform)
+    }
+
     pub fn area(&self) -> f64 {
         self.shape.area() * self.transform.scaling() * self.transform.scaling()
     }
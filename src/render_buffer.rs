@@ -2,6 +2,9 @@ use std::ops::{AddAssign, DivAssign, Index, IndexMut};
 
 use image::{Rgb, Rgb32FImage, RgbImage};
 use nalgebra::Vector3;
+
+use crate::shader::luminance;
+
 #[derive(Debug, Clone)]
 pub struct RenderBuffer {
     width: u32,
@@ -122,3 +125,125 @@ impl DivAssign<f64> for RenderBuffer {
         }
     }
 }
+
+/// Running mean and variance of the samples of a pixel, updated one sample at a time with
+/// Welford's algorithm. The variance is that of the luminance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PixelStatistics {
+    count: usize,
+    mean: Vector3<f64>,
+    mean_luminance: f64,
+    squared_deviations: f64,
+}
+
+impl PixelStatistics {
+    pub fn add_sample(&mut self, sample: Vector3<f64>) {
+        self.count += 1;
+        self.mean += (sample - self.mean) / self.count as f64;
+
+        let sample_luminance = luminance(&sample);
+        let delta = sample_luminance - self.mean_luminance;
+        self.mean_luminance += delta / self.count as f64;
+        self.squared_deviations += delta * (sample_luminance - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Vector3<f64> {
+        self.mean
+    }
+
+    /// Sample variance of the luminance.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.
+        } else {
+            self.squared_deviations / (self.count - 1) as f64
+        }
+    }
+
+    /// The standard error of the mean luminance relative to the mean luminance. A small offset
+    /// keeps black pixels from dividing by zero.
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        (self.variance() / self.count as f64).sqrt() / (self.mean_luminance.abs() + 1e-3)
+    }
+}
+
+/// Per pixel sample statistics, for renderers that vary the number of samples per pixel.
+#[derive(Debug, Clone)]
+pub struct StatisticsBuffer {
+    width: u32,
+    height: u32,
+    buffer: Vec<PixelStatistics>,
+}
+
+impl StatisticsBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            buffer: vec![PixelStatistics::default(); (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The mean of the samples of every pixel.
+    pub fn mean(&self) -> RenderBuffer {
+        self.to_render_buffer(|statistics| statistics.mean())
+    }
+
+    /// The number of samples of every pixel, in all three channels.
+    pub fn sample_counts(&self) -> RenderBuffer {
+        self.to_render_buffer(|statistics| Vector3::repeat(statistics.count() as f64))
+    }
+
+    /// Shows where the samples went, from blue for the fewest to red for the most samples.
+    pub fn heatmap(&self) -> RenderBuffer {
+        let counts = self.buffer.iter().map(PixelStatistics::count);
+        let (min, max) = (counts.clone().min().unwrap_or(0), counts.max().unwrap_or(0));
+        let range = (max - min).max(1) as f64;
+
+        self.to_render_buffer(|statistics| {
+            let t = (statistics.count() - min) as f64 / range;
+            Vector3::new(t, 1. - (2. * t - 1.).abs(), 1. - t)
+        })
+    }
+
+    fn to_render_buffer<F: Fn(&PixelStatistics) -> Vector3<f64>>(&self, f: F) -> RenderBuffer {
+        let mut render_buffer = RenderBuffer::new(self.width, self.height);
+        for x in 0..self.width {
+            for y in 0..self.height {
+                render_buffer[(x, y)] = f(&self[(x, y)]);
+            }
+        }
+        render_buffer
+    }
+}
+
+impl Index<(u32, u32)> for StatisticsBuffer {
+    type Output = PixelStatistics;
+
+    fn index(&self, (row, column): (u32, u32)) -> &Self::Output {
+        let index = row * self.height + column;
+        &self.buffer[index as usize]
+    }
+}
+
+impl IndexMut<(u32, u32)> for StatisticsBuffer {
+    fn index_mut(&mut self, (row, column): (u32, u32)) -> &mut Self::Output {
+        let index = row * self.height + column;
+        &mut self.buffer[index as usize]
+    }
+}
@@ -1,21 +1,61 @@
-use std::thread;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
 
-use crate::{RenderBuffer, Scene};
+use nalgebra::Vector3;
+use rand::{thread_rng, Rng};
 
+use crate::{
+    sampler::{IndependentSampler, Sampler},
+    Ray, RenderBuffer, Scene,
+};
+
+mod adaptive_renderer;
 mod backward_renderer;
 mod bdpt_renderer;
 mod depth_renderer;
+mod path_tracer;
+mod progressive_renderer;
 mod recursive_bdpt;
 mod simple_renderer;
+mod tile_renderer;
 
+pub use adaptive_renderer::AdaptiveRenderer;
 pub use backward_renderer::BackwardRenderer;
 pub use bdpt_renderer::BDPTRenderer;
 pub use depth_renderer::{DepthRenderMode, DepthRenderer};
+pub use path_tracer::PathTracer;
+pub use progressive_renderer::ProgressiveRenderer;
 pub use recursive_bdpt::RecursiveBDPT;
 pub use simple_renderer::SimpleRenderer;
+pub use tile_renderer::{Tile, TileRenderer};
 
 pub trait Renderer: Send + Sync + Sized {
-    fn render(&self, scene: &Scene) -> RenderBuffer;
+    /// Renders [`Renderer::num_samples`] samples per pixel and returns their average. Calls
+    /// with consecutive `sample_index` take consecutive samples from `sampler`, all random
+    /// numbers are drawn from it.
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer;
+
+    /// The number of samples per pixel of one [`Renderer::render_sample`].
+    fn num_samples(&self) -> usize {
+        1
+    }
+
+    /// Renders one sample per pixel with uniform random numbers.
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        let mut sampler = IndependentSampler::new(1, thread_rng().gen());
+        self.render_sample(scene, &mut sampler, 0)
+    }
 
     fn iterative(self, num_samples: usize) -> IterativeRenderer<Self> {
         IterativeRenderer::new(self, num_samples)
@@ -24,10 +64,118 @@ pub trait Renderer: Send + Sync + Sized {
     fn parallel(self, num_samples: usize) -> ParallelRenderer<Self> {
         ParallelRenderer::new(self, num_samples)
     }
+
+    fn progressive(self, max_passes: usize) -> ProgressiveRenderer<Self> {
+        ProgressiveRenderer::new(self, max_passes)
+    }
+
+    fn tiled(self, num_samples: usize) -> TileRenderer<Self>
+    where
+        Self: PixelRenderer,
+    {
+        TileRenderer::new(self, num_samples)
+    }
+
+    fn adaptive(self, min_samples: usize, max_samples: usize) -> AdaptiveRenderer<Self>
+    where
+        Self: PixelRenderer,
+    {
+        AdaptiveRenderer::new(self, min_samples, max_samples)
+    }
+}
+
+/// Renderers whose samples only add to the pixel they were started in, which lets them render
+/// any part of the image on its own.
+pub trait PixelRenderer: Renderer {
+    /// The color of one sample of `pixel`, the sample was already started on `sampler`.
+    fn render_pixel(
+        &self,
+        scene: &Scene,
+        pixel: (u32, u32),
+        sampler: &mut dyn Sampler,
+    ) -> Vector3<f64>;
+}
+
+/// Light transport along a single camera ray. Every integrator is a [`PixelRenderer`], the
+/// loop over the pixels, the placement of samples through the camera's
+/// [`Filter`](crate::filter::Filter) and the wrappers for sample counts and parallelism are
+/// shared.
+pub trait Integrator: Send + Sync {
+    /// The radiance arriving at the camera along `ray`.
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vector3<f64>;
+}
+
+impl<I: Integrator> PixelRenderer for I {
+    fn render_pixel(
+        &self,
+        scene: &Scene,
+        (x, y): (u32, u32),
+        sampler: &mut dyn Sampler,
+    ) -> Vector3<f64> {
+        let ray = scene.camera.get_ray(x, y, sampler);
+        self.radiance(&ray, scene, sampler)
+    }
+}
+
+impl<I: Integrator> Renderer for I {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        render_pixels(scene, sampler, sample_index, |pixel, sampler| {
+            self.render_pixel(scene, pixel, sampler)
+        })
+    }
 }
+
+/// Starts sample `sample_index` of every pixel in turn and stores the color `render_pixel`
+/// returns for it.
+pub(crate) fn render_pixels<F>(
+    scene: &Scene,
+    sampler: &mut dyn Sampler,
+    sample_index: usize,
+    mut render_pixel: F,
+) -> RenderBuffer
+where
+    F: FnMut((u32, u32), &mut dyn Sampler) -> Vector3<f64>,
+{
+    let width = scene.camera.width;
+    let height = scene.camera.height;
+
+    let mut render_buffer = RenderBuffer::new(width, height);
+
+    for x in 0..width {
+        for y in 0..height {
+            sampler.start_pixel_sample((x, y), sample_index);
+            render_buffer[(x, y)] = render_pixel((x, y), sampler);
+        }
+    }
+
+    render_buffer
+}
+
+/// Russian roulette: ends a path at random with a probability that grows as its throughput
+/// falls. Returns the probability that the path goes on, which the throughput has to be divided
+/// by to stay unbiased, or `None` if the path ends.
+pub(crate) fn russian_roulette(
+    throughput: &Vector3<f64>,
+    sampler: &mut dyn Sampler,
+) -> Option<f64> {
+    let survival = throughput.max().min(0.95);
+    (sampler.get_1d() < survival).then_some(survival)
+}
+
+/// A sampler with random seed for the wrappers that aren't given one.
+fn default_sampler(num_samples: usize) -> Box<dyn Sampler> {
+    Box::new(IndependentSampler::new(num_samples, thread_rng().gen()))
+}
+
 pub struct IterativeRenderer<R: Renderer> {
     renderer: R,
     num_samples: usize,
+    sampler: Box<dyn Sampler>,
 }
 
 impl<R: Renderer> IterativeRenderer<R> {
@@ -35,27 +183,84 @@ impl<R: Renderer> IterativeRenderer<R> {
         Self {
             renderer,
             num_samples,
+            sampler: default_sampler(num_samples),
         }
     }
+
+    /// Draws the samples from `sampler` instead of uniform random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// Seeds the sampler, which makes the render the same every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.sampler.set_seed(seed);
+        self
+    }
 }
 
 impl<R: Renderer> Renderer for IterativeRenderer<R> {
-    fn render(&self, scene: &Scene) -> RenderBuffer {
-        let mut render_buffer = self.renderer.render(scene);
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        let first = sample_index * self.num_samples;
+        let mut render_buffer = self.renderer.render_sample(scene, sampler, first);
 
-        for _ in 0..self.num_samples - 1 {
-            render_buffer += self.renderer.render(scene);
+        for index in first + 1..first + self.num_samples {
+            render_buffer += self.renderer.render_sample(scene, sampler, index);
         }
 
         render_buffer /= self.num_samples as f64;
         render_buffer
     }
+
+    fn num_samples(&self) -> usize {
+        self.num_samples * self.renderer.num_samples()
+    }
+
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        let mut sampler = self.sampler.clone_box();
+        self.render_sample(scene, sampler.as_mut(), 0)
+    }
+}
+
+/// Adds up sample buffers in the order of their indices, whatever order they arrive in, so the
+/// rounding of the sum doesn't depend on how the samples were split up.
+struct OrderedSum {
+    next: usize,
+    pending: BTreeMap<usize, RenderBuffer>,
+    sum: RenderBuffer,
+}
+
+impl OrderedSum {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            next: 0,
+            pending: BTreeMap::new(),
+            sum: RenderBuffer::new(width, height),
+        }
+    }
+
+    fn add(&mut self, index: usize, render_buffer: RenderBuffer) {
+        self.pending.insert(index, render_buffer);
+        while let Some(render_buffer) = self.pending.remove(&self.next) {
+            self.sum += render_buffer;
+            self.next += 1;
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Renders the samples on several threads. With a seeded sampler the result is the same for
+/// any number of threads, and the same as that of an [`IterativeRenderer`].
 pub struct ParallelRenderer<R: Renderer> {
     renderer: R,
     num_samples: usize,
+    sampler: Box<dyn Sampler>,
+    num_threads: Option<usize>,
 }
 
 impl<R: Renderer> ParallelRenderer<R> {
@@ -63,55 +268,76 @@ impl<R: Renderer> ParallelRenderer<R> {
         Self {
             renderer,
             num_samples,
+            sampler: default_sampler(num_samples),
+            num_threads: None,
         }
     }
+
+    /// Limits the number of threads, which defaults to the available parallelism.
+    pub fn with_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = Some(num_threads.max(1));
+        self
+    }
+
+    /// Draws the samples from `sampler` instead of uniform random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// Seeds the sampler, which makes the render the same every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.sampler.set_seed(seed);
+        self
+    }
 }
 
 impl<R: Renderer> Renderer for ParallelRenderer<R> {
-    fn render(&self, scene: &Scene) -> RenderBuffer {
-        let width = scene.camera.width;
-        let height = scene.camera.height;
-
-        let mut render_buffer = RenderBuffer::new(width, height);
-
-        let num_threads: usize = thread::available_parallelism().unwrap().into();
-        let num_threads = num_threads.min(self.num_samples);
-
-        let division = self.num_samples / num_threads;
-        let remainder = self.num_samples % num_threads;
-        let samples_per_thread = (0..num_threads)
-            .map(|i| {
-                if i < remainder {
-                    division + 1
-                } else {
-                    division
-                }
-            })
-            .collect::<Vec<_>>();
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        let num_threads = self
+            .num_threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from))
+            .min(self.num_samples);
+
+        // Threads take the next sample until all are rendered.
+        let first = sample_index * self.num_samples;
+        let next_sample = AtomicUsize::new(0);
+        let sum = Mutex::new(OrderedSum::new(scene.camera.width, scene.camera.height));
 
         thread::scope(|s| {
-            let thread_handles = samples_per_thread
-                .into_iter()
-                .map(|num_samples| {
-                    s.spawn(move || {
-                        let mut render_buffer = self.renderer.render(scene);
-
-                        for _ in 0..num_samples - 1 {
-                            render_buffer += self.renderer.render(scene);
-                        }
-
-                        render_buffer
-                    })
-                })
-                .collect::<Vec<_>>();
-
-            for handle in thread_handles {
-                let thread_render_buffer = handle.join().unwrap();
-                render_buffer += thread_render_buffer;
+            for _ in 0..num_threads {
+                let mut sampler = sampler.clone_box();
+                let (next_sample, sum) = (&next_sample, &sum);
+                s.spawn(move || loop {
+                    let index = next_sample.fetch_add(1, Ordering::Relaxed);
+                    if index >= self.num_samples {
+                        break;
+                    }
+
+                    let render_buffer =
+                        self.renderer
+                            .render_sample(scene, sampler.as_mut(), first + index);
+                    sum.lock().unwrap().add(index, render_buffer);
+                });
             }
         });
 
+        let mut render_buffer = sum.into_inner().unwrap().sum;
         render_buffer /= self.num_samples as f64;
         render_buffer
     }
+
+    fn num_samples(&self) -> usize {
+        self.num_samples * self.renderer.num_samples()
+    }
+
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        let mut sampler = self.sampler.clone_box();
+        self.render_sample(scene, sampler.as_mut(), 0)
+    }
 }
@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+
+use crate::{
+    render_buffer::{PixelStatistics, StatisticsBuffer},
+    sampler::Sampler,
+    RenderBuffer, Scene,
+};
+
+use super::{default_sampler, tile_renderer::render_in_tiles, PixelRenderer, Renderer};
+
+/// Spends samples where the image is noisy. Every pixel first takes `min_samples`, then more
+/// in batches of `min_samples` until the relative standard error of its mean falls below the
+/// threshold or it reaches `max_samples`. Pixels are independent of each other and are
+/// rendered in tiles like with a [`TileRenderer`](super::TileRenderer).
+pub struct AdaptiveRenderer<R: PixelRenderer> {
+    renderer: R,
+    min_samples: usize,
+    max_samples: usize,
+    threshold: f64,
+    sampler: Box<dyn Sampler>,
+    tile_size: u32,
+    num_threads: Option<usize>,
+}
+
+impl<R: PixelRenderer> AdaptiveRenderer<R> {
+    pub fn new(renderer: R, min_samples: usize, max_samples: usize) -> Self {
+        let min_samples = min_samples.max(2);
+        let max_samples = max_samples.max(min_samples);
+        Self {
+            renderer,
+            min_samples,
+            max_samples,
+            threshold: 0.05,
+            sampler: default_sampler(max_samples),
+            tile_size: 32,
+            num_threads: None,
+        }
+    }
+
+    /// Sets the relative error pixels are refined to, 0.05 by default.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Draws the samples from `sampler` instead of uniform random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// Seeds the sampler, which makes the render the same every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.sampler.set_seed(seed);
+        self
+    }
+
+    /// Sets the width and height of the tiles in pixels, 32 by default.
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// Limits the number of threads, which defaults to the available parallelism.
+    pub fn with_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = Some(num_threads.max(1));
+        self
+    }
+
+    /// Renders the image and returns the statistics of every pixel. Their mean is the image and
+    /// [`StatisticsBuffer::heatmap`] shows where the samples went.
+    pub fn render_statistics(&self, scene: &Scene) -> StatisticsBuffer {
+        let mut sampler = self.sampler.clone_box();
+        self.render_statistics_with(scene, sampler.as_mut(), 0)
+    }
+
+    fn render_statistics_with(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> StatisticsBuffer {
+        let width = scene.camera.width;
+        let height = scene.camera.height;
+
+        let first = sample_index * self.max_samples;
+        let statistics = Mutex::new(StatisticsBuffer::new(width, height));
+
+        render_in_tiles(
+            (width, height),
+            self.tile_size,
+            self.num_threads,
+            sampler,
+            |pixel, sampler| self.render_pixel(scene, pixel, sampler, first),
+            |tile, values| {
+                let mut statistics = statistics.lock().unwrap();
+                for (pixel, value) in tile.pixels().zip(values) {
+                    statistics[pixel] = value;
+                }
+            },
+        );
+
+        statistics.into_inner().unwrap()
+    }
+
+    /// Samples a pixel until it is converged, starting at sample index `first`.
+    fn render_pixel(
+        &self,
+        scene: &Scene,
+        pixel: (u32, u32),
+        sampler: &mut dyn Sampler,
+        first: usize,
+    ) -> PixelStatistics {
+        let mut statistics = PixelStatistics::default();
+        while statistics.count() < self.max_samples
+            && (statistics.count() < self.min_samples
+                || statistics.relative_error() > self.threshold)
+        {
+            let batch = self.min_samples.min(self.max_samples - statistics.count());
+            for _ in 0..batch {
+                sampler.start_pixel_sample(pixel, first + statistics.count());
+                statistics.add_sample(self.renderer.render_pixel(scene, pixel, sampler));
+            }
+        }
+        statistics
+    }
+}
+
+impl<R: PixelRenderer> Renderer for AdaptiveRenderer<R> {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        self.render_statistics_with(scene, sampler, sample_index)
+            .mean()
+    }
+
+    /// The most samples a pixel can take, most take fewer.
+    fn num_samples(&self) -> usize {
+        self.max_samples
+    }
+
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        self.render_statistics(scene).mean()
+    }
+}
@@ -1,35 +1,63 @@
-use crate::{Ray, RenderBuffer, Renderer, Scene};
+use crate::{
+    renderer::{russian_roulette, Integrator},
+    Ray, Sampler, Scene,
+};
 
 use na::Vector3;
 use nalgebra as na;
 
+/// Follows paths from the camera until they leave the scene or reach `max_bounces`. After
+/// `roulette_depth` bounces, dim paths are ended early with Russian roulette.
 pub struct BackwardRenderer {
     pub max_bounces: u8,
+    pub roulette_depth: u8,
 }
 
 impl BackwardRenderer {
     pub fn new(max_bounces: u8) -> Self {
-        Self { max_bounces }
+        Self {
+            max_bounces,
+            roulette_depth: 3,
+        }
+    }
+
+    /// Sets the number of bounces before Russian roulette starts, 3 by default.
+    pub fn with_roulette_depth(mut self, roulette_depth: u8) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
     }
-    fn sample_color(&self, ray: &Ray, scene: &Scene) -> Vector3<f64> {
+}
+
+impl Integrator for BackwardRenderer {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vector3<f64> {
         let mut current_color_filter = Vector3::new(1., 1., 1.);
         let mut current_emission = Vector3::zeros();
         let mut current_ray = *ray;
 
-        for _bounce in 0..self.max_bounces {
+        for bounce in 0..self.max_bounces {
             if let Some((object, intersection)) = scene.intersection(&current_ray) {
-                let interaction = object.material().interact(&current_ray, &intersection);
+                let interaction = object
+                    .material()
+                    .interact(&current_ray, &intersection, sampler);
 
                 current_emission += interaction.emission.component_mul(&current_color_filter);
                 current_color_filter.component_mul_assign(&interaction.filter);
 
-                if let Some(outgoing) = interaction.outgoing {
-                    current_ray = outgoing;
-                } else {
+                let Some(outgoing) = interaction.outgoing else {
                     break;
+                };
+                current_ray = outgoing;
+
+                if bounce >= self.roulette_depth {
+                    let Some(survival) = russian_roulette(&current_color_filter, sampler) else {
+                        break;
+                    };
+                    current_color_filter /= survival;
                 }
             } else {
-                current_color_filter *= 0.;
+                current_emission += scene
+                    .background(&current_ray.direction)
+                    .component_mul(&current_color_filter);
                 break;
             }
         }
@@ -37,22 +65,3 @@ impl BackwardRenderer {
         current_emission
     }
 }
-
-impl Renderer for BackwardRenderer {
-    fn render(&self, scene: &Scene) -> RenderBuffer {
-        let width = scene.camera.width;
-        let height = scene.camera.height;
-
-        let mut render_buffer = RenderBuffer::new(width, height);
-
-        for x in 0..width {
-            for y in 0..height {
-                let ray = scene.camera.get_ray(x, y);
-
-                render_buffer[(x, y)] = self.sample_color(&ray, scene);
-            }
-        }
-
-        render_buffer
-    }
-}
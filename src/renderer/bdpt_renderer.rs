@@ -1,163 +1,847 @@
-use na::{Point3, Vector3};
+use std::f64::consts::PI;
+
+use na::{Point3, Vector2, Vector3};
 use nalgebra as na;
 
-use crate::{Material, Ray, RenderBuffer, Renderer, Scene};
+use crate::{
+    light::sample_disk_outside, renderer::russian_roulette, Light, Material, Ray, RenderBuffer,
+    Renderer, Sampler, Scene, ShadingPoint,
+};
 
-#[derive(Clone, Copy)]
-struct PathVertex<'a> {
-    pub position: Point3<f64>,
-    pub normal: Vector3<f64>,
-    pub incoming: Vector3<f64>,
-    pub material: &'a Material,
-    pub accumulated_absorption: Vector3<f64>,
-    pub accumulated_emission: Vector3<f64>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PathDirection {
     CameraPath,
     LightPath,
 }
 
+#[derive(Clone, Copy)]
+struct PathVertex<'a> {
+    kind: VertexKind,
+    position: Point3<f64>,
+    normal: Vector3<f64>,
+    uv: Vector2<f64>,
+    /// Direction of the ray that arrived at this vertex.
+    incoming: Vector3<f64>,
+    material: Option<&'a Material>,
+    /// The light that started the subpath, on light vertices.
+    light: Option<&'a Light>,
+    /// Area density of picking this point when sampling a position on a light.
+    emitter_pdf: f64,
+    /// Contribution of the subpath up to this vertex divided by its density.
+    throughput: Vector3<f64>,
+    /// Area density of sampling this vertex from the previous one along its own subpath.
+    pdf_forward: f64,
+    /// Area density of sampling this vertex when coming from the other end of the path.
+    pdf_reverse: f64,
+    delta: bool,
+}
+
+impl<'a> PathVertex<'a> {
+    fn camera(position: Point3<f64>) -> Self {
+        Self {
+            kind: VertexKind::Camera,
+            position,
+            normal: Vector3::zeros(),
+            uv: Vector2::zeros(),
+            incoming: Vector3::zeros(),
+            material: None,
+            light: None,
+            emitter_pdf: 0.,
+            throughput: Vector3::new(1., 1., 1.),
+            pdf_forward: 1.,
+            pdf_reverse: 0.,
+            delta: false,
+        }
+    }
+
+    /// A vertex on a light, where `pdf_position` includes the probability of picking the light.
+    /// Point lights have no surface and their position is always the same, so that density is
+    /// just the probability of picking them.
+    fn light(
+        light: &'a Light,
+        point: &ShadingPoint,
+        material: Option<&'a Material>,
+        pdf_position: f64,
+    ) -> Self {
+        Self {
+            kind: VertexKind::Light,
+            position: point.position,
+            normal: point.normal,
+            uv: point.uv,
+            incoming: Vector3::zeros(),
+            material,
+            light: Some(light),
+            emitter_pdf: pdf_position,
+            throughput: Vector3::repeat(1. / pdf_position),
+            pdf_forward: pdf_position,
+            pdf_reverse: 0.,
+            delta: false,
+        }
+    }
+
+    /// A vertex at a sampled point on `light`, picked with `probability`. Directional lights have
+    /// no position and are connected to without a light vertex.
+    fn sample_light(
+        scene: &'a Scene,
+        light: &'a Light,
+        probability: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Self> {
+        match light {
+            Light::Area(index) => {
+                let object = &scene.objects[*index];
+                let point = object.sample_surface_point(sampler);
+                Some(Self::light(
+                    light,
+                    &point,
+                    Some(object.material()),
+                    probability / object.area(),
+                ))
+            }
+            Light::Point(point_light) => {
+                let point = ShadingPoint {
+                    position: point_light.position(),
+                    normal: Vector3::zeros(),
+                    uv: Vector2::zeros(),
+                };
+                Some(Self::light(light, &point, None, probability))
+            }
+            Light::Directional(_) | Light::Environment(_) => None,
+        }
+    }
+
+    /// A vertex for a camera path that left the scene along `ray`. It stands for all lights that
+    /// rays leaving the scene see, so its density sums over them.
+    fn infinite(scene: &'a Scene, ray: &Ray, throughput: Vector3<f64>, pdf: f64) -> Self {
+        let (light, _) = scene
+            .infinite_lights()
+            .next()
+            .expect("The scene has no lights that rays leaving it see");
+
+        Self {
+            kind: VertexKind::Light,
+            position: ray.origin + ray.direction,
+            normal: Vector3::zeros(),
+            uv: Vector2::zeros(),
+            incoming: ray.direction,
+            material: None,
+            light: Some(light),
+            emitter_pdf: scene.infinite_light_pdf(&ray.direction),
+            throughput,
+            pdf_forward: pdf,
+            pdf_reverse: 0.,
+            delta: false,
+        }
+    }
+
+    fn is_delta_light(&self) -> bool {
+        self.light.is_some_and(Light::is_delta_position)
+    }
+
+    /// Vertices on lights infinitely far away only stand for a direction. Densities towards them
+    /// are solid angle densities and densities from them area densities on a disk facing the
+    /// scene.
+    fn is_infinite_light(&self) -> bool {
+        matches!(
+            self.light,
+            Some(Light::Directional(_) | Light::Environment(_))
+        )
+    }
+
+    fn shading_point(&self) -> ShadingPoint {
+        ShadingPoint {
+            position: self.position,
+            normal: self.normal,
+            uv: self.uv,
+        }
+    }
+
+    fn is_on_surface(&self) -> bool {
+        self.kind != VertexKind::Camera && !self.is_delta_light() && !self.is_infinite_light()
+    }
+
+    /// The cosine between the normal and `direction`, or 1 for vertices that aren't on a surface.
+    fn cosine(&self, direction: &Vector3<f64>) -> f64 {
+        if self.is_on_surface() {
+            self.normal.dot(direction).abs()
+        } else {
+            1.
+        }
+    }
+
+    /// Radiance emitted back along the ray that arrived at this vertex.
+    fn emission(&self, scene: &Scene) -> Vector3<f64> {
+        if self.is_infinite_light() {
+            return scene.background(&self.incoming);
+        }
+        self.material
+            .map(|material| material.emission(&self.shading_point(), &-self.incoming))
+            .unwrap_or_else(Vector3::zeros)
+    }
+
+    /// Converts a solid angle density at this vertex into an area density at `next`.
+    fn convert_density(&self, pdf: f64, next: &PathVertex) -> f64 {
+        if next.is_infinite_light() {
+            return pdf;
+        }
+        let difference = next.position - self.position;
+        if self.is_infinite_light() {
+            return pdf * next.cosine(&difference.normalize());
+        }
+
+        let distance_squared = difference.magnitude_squared();
+        if distance_squared == 0. {
+            return 0.;
+        }
+
+        let mut pdf = pdf / distance_squared;
+        if next.is_on_surface() {
+            pdf *= next.normal.dot(&difference).abs() / distance_squared.sqrt();
+        }
+        pdf
+    }
+
+    /// The BSDF for light traveling between this vertex and `next`, in the direction of transport
+    /// of the subpath this vertex belongs to. On light vertices this is the emitted radiance.
+    fn bsdf(&self, next: &PathVertex, direction: PathDirection) -> Vector3<f64> {
+        let to_next = (next.position - self.position).normalize();
+
+        if let Some(Light::Point(light)) = self.light {
+            return light.intensity(&to_next);
+        }
+        let Some(material) = self.material else {
+            return Vector3::zeros();
+        };
+        if self.kind == VertexKind::Light {
+            return material.emission(&self.shading_point(), &to_next);
+        }
+
+        match direction {
+            PathDirection::CameraPath => {
+                material.evaluate(&self.incoming, &to_next, &self.shading_point())
+            }
+            PathDirection::LightPath => {
+                material.evaluate(&-to_next, &-self.incoming, &self.shading_point())
+            }
+        }
+    }
+
+    /// Area density of emitting light from this vertex towards `next`.
+    fn light_pdf(&self, scene: &Scene, next: &PathVertex) -> f64 {
+        let direction = (next.position - self.position).normalize();
+
+        if self.is_infinite_light() {
+            let (_, radius) = scene.bounding_sphere();
+            return self.convert_density(1. / (PI * radius * radius), next);
+        }
+
+        if let Some(Light::Point(light)) = self.light {
+            return self.convert_density(light.direction_pdf(&direction), next);
+        }
+        let Some(emission) = self.material.and_then(Material::emitter) else {
+            return 0.;
+        };
+
+        self.convert_density(emission.direction_pdf(&self.normal, &direction), next)
+    }
+
+    /// Area density of sampling `next` from this vertex, having arrived from `previous`.
+    fn pdf(&self, scene: &Scene, previous: Option<&PathVertex>, next: &PathVertex) -> f64 {
+        match self.kind {
+            VertexKind::Camera => {
+                let direction = (next.position - self.position).normalize();
+                self.convert_density(scene.camera.direction_pdf(&direction), next)
+            }
+            VertexKind::Light => self.light_pdf(scene, next),
+            VertexKind::Surface => {
+                let (Some(material), Some(previous)) = (self.material, previous) else {
+                    return 0.;
+                };
+                let incoming = (self.position - previous.position).normalize();
+                let outgoing = (next.position - self.position).normalize();
+
+                let pdf = material.scattering_pdf(&incoming, &outgoing, &self.normal);
+                self.convert_density(pdf, next)
+            }
+        }
+    }
+}
+
+/// Bidirectional path tracer that combines all ways of connecting a camera subpath with a light
+/// subpath using multiple importance sampling, following Veach's formulation with area measure
+/// densities. Light subpaths are also connected directly to the camera when it is a pinhole
+/// camera. Paths scatter at most `max_bounces` times, and after `roulette_depth` bounces dim
+/// subpaths are ended early with Russian roulette.
 pub struct BDPTRenderer {
     max_bounces: u8,
-    camera_material: Material,
+    roulette_depth: u8,
 }
 
 impl BDPTRenderer {
     pub fn new(max_bounces: u8) -> Self {
         Self {
             max_bounces,
-            camera_material: Material::new(Vector3::new(1., 1., 1.), 0., false),
+            roulette_depth: 3,
         }
     }
 
-    fn sample_path<'a>(
-        &'a self,
-        ray: &'a Ray,
-        scene: &'a Scene,
-        material: &'a Material,
-        path_direction: PathDirection,
-    ) -> Vec<PathVertex<'a>> {
-        let absorption = material.absorption_color(&ray.origin.coords);
-        let emission = material.emission_color();
+    /// Sets the number of bounces of a subpath before Russian roulette starts, 3 by default.
+    pub fn with_roulette_depth(mut self, roulette_depth: u8) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
 
-        let first_vertex = PathVertex {
-            position: ray.origin,
-            normal: ray.direction,
-            incoming: -ray.direction,
-            material,
-            accumulated_absorption: Vector3::new(1., 1., 1.),
-            accumulated_emission: emission,
+    /// Extends `path` from its first vertex along `ray`. Paths that start at the camera are
+    /// camera paths, all others are light paths.
+    fn random_walk<'a>(
+        &self,
+        scene: &'a Scene,
+        ray: &Ray,
+        throughput: Vector3<f64>,
+        pdf: f64,
+        path: &mut Vec<PathVertex<'a>>,
+        sampler: &mut dyn Sampler,
+    ) {
+        let (direction, max_vertices) = if path[0].kind == VertexKind::Camera {
+            (PathDirection::CameraPath, self.max_bounces as usize + 1)
+        } else {
+            (PathDirection::LightPath, self.max_bounces as usize)
         };
+        let mut current_ray = *ray;
+        let mut throughput = throughput;
+        let mut pdf_forward = pdf;
+        // Light paths start out in units of power, so Russian roulette looks at how much of the
+        // starting throughput is left.
+        let mut attenuation = Vector3::new(1., 1., 1.);
 
-        let mut current_path = vec![first_vertex];
+        for bounce in 0..max_vertices {
+            let previous = path[path.len() - 1];
+            let Some((object, intersection)) = scene.intersection(&current_ray) else {
+                if direction == PathDirection::CameraPath
+                    && scene.infinite_lights().next().is_some()
+                {
+                    // The density stays a solid angle density, see `convert_density`.
+                    path.push(PathVertex::infinite(
+                        scene,
+                        &current_ray,
+                        throughput,
+                        pdf_forward,
+                    ));
+                }
+                break;
+            };
+            let material = object.material();
 
-        let mut current_ray = *ray;
-        let mut accumulated_emission = emission;
-        let mut accumulated_absorption = absorption;
-        for _bounce in 0..self.max_bounces {
-            if let Some((object, intersection)) = scene.intersection(&current_ray) {
-                let interaction = object.material().interact(&current_ray, &intersection);
-                let current_absorption = interaction.filter;
-                let current_emission = interaction.emission;
-
-                accumulated_emission = match path_direction {
-                    PathDirection::CameraPath => {
-                        current_emission.component_mul(&accumulated_absorption)
-                            + accumulated_emission
-                    }
+            let mut vertex = PathVertex {
+                kind: VertexKind::Surface,
+                position: intersection.position,
+                normal: intersection.normal,
+                uv: intersection.uv,
+                incoming: current_ray.direction,
+                material: Some(material),
+                light: None,
+                emitter_pdf: object.light_probability() / object.area(),
+                throughput,
+                pdf_forward: 0.,
+                pdf_reverse: 0.,
+                delta: false,
+            };
+            vertex.pdf_forward = previous.convert_density(pdf_forward, &vertex);
+            path.push(vertex);
+
+            let incoming = current_ray.direction;
+            let normal = intersection.normal;
+            let position = intersection.position;
+            let point = ShadingPoint::from(&intersection);
+
+            let Some(sample) = material.sample_scattering(&incoming, &point, sampler) else {
+                break;
+            };
+
+            let (weight, pdf_reverse) = if sample.specular {
+                let last = path.len() - 1;
+                path[last].delta = true;
+                pdf_forward = 0.;
+                match direction {
+                    PathDirection::CameraPath => (sample.weight, 0.),
+                    // Importance is not scaled when crossing into a different medium.
+                    PathDirection::LightPath => (sample.weight * sample.eta * sample.eta, 0.),
+                }
+            } else {
+                let weight = match direction {
+                    PathDirection::CameraPath => sample.weight,
                     PathDirection::LightPath => {
-                        accumulated_emission.component_mul(&current_absorption) + current_emission
+                        // Light paths carry importance, which scatters with the adjoint BSDF.
+                        let adjoint = material.evaluate(&-sample.direction, &-incoming, &point);
+                        adjoint * sample.direction.dot(&normal).abs() / sample.pdf
                     }
                 };
-                accumulated_absorption.component_mul_assign(&current_absorption);
-
-                let vertex = PathVertex {
-                    position: intersection.position,
-                    normal: intersection.normal.normalize(),
-                    incoming: current_ray.direction,
-                    material: object.material(),
-                    accumulated_absorption,
-                    accumulated_emission,
-                };
+                pdf_forward = sample.pdf;
+                (
+                    weight,
+                    material.scattering_pdf(&-sample.direction, &-incoming, &normal),
+                )
+            };
+            throughput.component_mul_assign(&weight);
+            attenuation.component_mul_assign(&weight);
+
+            let last = path.len() - 1;
+            path[last - 1].pdf_reverse = path[last].convert_density(pdf_reverse, &path[last - 1]);
 
-                current_path.push(vertex);
-                if let Some(outgoing) = interaction.outgoing {
-                    current_ray = outgoing;
-                } else {
+            // The densities stay those of the path without Russian roulette, which leaves the
+            // MIS weights as they are.
+            if bounce >= self.roulette_depth as usize {
+                let Some(survival) = russian_roulette(&attenuation, sampler) else {
                     break;
-                }
-            } else {
-                break;
+                };
+                throughput /= survival;
+                attenuation /= survival;
             }
-        }
 
-        current_path
+            current_ray = Ray {
+                origin: position + sample.direction * 0.001,
+                direction: sample.direction,
+            };
+        }
     }
 
-    fn sample_color(&self, ray: &Ray, scene: &Scene) -> Vector3<f64> {
-        let camera_path =
-            self.sample_path(ray, scene, &self.camera_material, PathDirection::CameraPath);
-
-        let light = scene.random_light();
+    fn camera_path<'a>(
+        &self,
+        scene: &'a Scene,
+        x: u32,
+        y: u32,
+        sampler: &mut dyn Sampler,
+    ) -> Vec<PathVertex<'a>> {
+        let ray = scene.camera.get_ray(x, y, sampler);
+        let origin = if scene.camera.is_pinhole() {
+            scene.camera.position()
+        } else {
+            ray.origin
+        };
 
-        let light_ray = light.sample_emissive_ray();
-        let light_path = self.sample_path(
-            &light_ray,
+        let mut path = vec![PathVertex::camera(origin)];
+        let pdf = scene.camera.direction_pdf(&ray.direction);
+        self.random_walk(
             scene,
-            light.material(),
-            PathDirection::LightPath,
+            &ray,
+            Vector3::new(1., 1., 1.),
+            pdf,
+            &mut path,
+            sampler,
         );
-        let mut total_importance = 1.; //1. / camera_path.len() as f64;
-        let mut total_light =
-            total_importance * camera_path[camera_path.len() - 1].accumulated_emission;
-
-        for vertex_camera in &camera_path[1..] {
-            for vertex_light in &light_path {
-                if vertex_camera.normal.dot(&vertex_light.normal) < 0.
-                    && scene.is_visible(&vertex_camera.position, &vertex_light.position)
-                {
-                    let current_light = vertex_light
-                        .accumulated_emission
-                        .component_mul(&vertex_camera.accumulated_absorption)
-                        + vertex_camera.accumulated_emission;
-
-                    let difference = (vertex_light.position - vertex_camera.position).normalize();
-                    //let mut importance = 1. / (i + light_path.len() - j) as f64;
-                    let importance = vertex_camera.material.likelihood(
-                        &vertex_camera.incoming,
-                        &difference,
-                        &vertex_camera.normal,
-                    ) * vertex_light.material.likelihood(
-                        &vertex_light.incoming,
-                        &-difference,
-                        &vertex_light.normal,
-                    );
-                    total_light += current_light * importance;
-                    total_importance += importance;
+        path
+    }
+
+    fn light_path<'a>(&self, scene: &'a Scene, sampler: &mut dyn Sampler) -> Vec<PathVertex<'a>> {
+        let Some((light, probability)) = scene.sample_light(sampler.get_1d()) else {
+            return Vec::new();
+        };
+
+        let (vertex, ray, throughput, pdf_direction) = match light {
+            Light::Area(index) => {
+                let object = &scene.objects[*index];
+                let material = object.material();
+                let (point, ray) = object.sample_emissive_ray(sampler);
+                let direction = ray.direction;
+                let pdf_direction = material.emitter().map_or(0., |emission| {
+                    emission.direction_pdf(&point.normal, &direction)
+                });
+
+                let vertex =
+                    PathVertex::light(light, &point, Some(material), probability / object.area());
+                if pdf_direction == 0. {
+                    return vec![vertex];
                 }
+                let throughput = vertex
+                    .throughput
+                    .component_mul(&material.emission(&point, &direction))
+                    * direction.dot(&point.normal).abs()
+                    / pdf_direction;
+                (vertex, ray, throughput, pdf_direction)
+            }
+            Light::Point(point_light) => {
+                let direction = point_light.sample_direction(sampler.get_2d());
+                let pdf_direction = point_light.direction_pdf(&direction);
+
+                let Some(vertex) = PathVertex::sample_light(scene, light, probability, sampler)
+                else {
+                    return Vec::new();
+                };
+                if pdf_direction == 0. {
+                    return vec![vertex];
+                }
+                let ray = Ray {
+                    origin: vertex.position,
+                    direction,
+                };
+                let throughput = vertex
+                    .throughput
+                    .component_mul(&point_light.intensity(&direction))
+                    / pdf_direction;
+                (vertex, ray, throughput, pdf_direction)
+            }
+            // Directional lights without a size can't be hit, they are only reached by
+            // connecting camera paths to them.
+            Light::Directional(directional) if directional.is_delta() => return Vec::new(),
+            Light::Directional(_) | Light::Environment(_) => {
+                let (center, _) = scene.bounding_sphere();
+                let Some(sample) = light.sample_incident(scene, &center, sampler) else {
+                    return Vec::new();
+                };
+                let to_light = sample.direction;
+                let (origin, pdf_position) =
+                    sample_disk_outside(scene, &to_light, sampler.get_2d());
+
+                let point = ShadingPoint {
+                    position: origin,
+                    normal: Vector3::zeros(),
+                    uv: Vector2::zeros(),
+                };
+                let mut vertex = PathVertex::light(light, &point, None, probability * sample.pdf);
+                let throughput = vertex.throughput.component_mul(&sample.radiance) / pdf_position;
+
+                // Camera paths that leave the scene find all of these lights at once.
+                vertex.pdf_forward = scene.infinite_light_pdf(&to_light);
+                vertex.emitter_pdf = vertex.pdf_forward;
+
+                let ray = Ray {
+                    origin,
+                    direction: -to_light,
+                };
+                (vertex, ray, throughput, pdf_position)
+            }
+        };
+
+        let mut path = vec![vertex];
+        self.random_walk(scene, &ray, throughput, pdf_direction, &mut path, sampler);
+        path
+    }
+
+    /// Evaluates the path made of the first `s` light vertices and the first `t` camera vertices.
+    /// Returns the unweighted contribution and the vertex that was sampled to replace one of the
+    /// path ends, if any.
+    fn connect<'a>(
+        scene: &'a Scene,
+        light_path: &[PathVertex<'a>],
+        camera_path: &[PathVertex<'a>],
+        s: usize,
+        t: usize,
+        sampler: &mut dyn Sampler,
+    ) -> (Vector3<f64>, Option<PathVertex<'a>>) {
+        let none = (Vector3::zeros(), None);
+
+        if s == 0 {
+            // The camera path found a light on its own.
+            let vertex = &camera_path[t - 1];
+            return (
+                vertex.throughput.component_mul(&vertex.emission(scene)),
+                None,
+            );
+        }
+
+        if t == 1 {
+            // Connect the light path to the camera.
+            let Some(vertex) = light_path.get(s - 1) else {
+                return none;
+            };
+            // Point lights are not visible, as in the path tracer, and the environment is seen by
+            // camera paths that leave the scene.
+            if vertex.delta
+                || vertex.is_delta_light()
+                || vertex.is_infinite_light()
+                || !scene.camera.is_pinhole()
+            {
+                return none;
+            }
+
+            let camera = PathVertex::camera(scene.camera.position());
+            let difference = camera.position - vertex.position;
+            let distance = difference.magnitude();
+            let direction = difference / distance;
+
+            let importance = scene.camera.direction_pdf(&-direction);
+            if importance == 0. || scene.camera.raster_position(&vertex.position).is_none() {
+                return none;
             }
+
+            let scattering = vertex.bsdf(&camera, PathDirection::LightPath);
+            let cosine = vertex.cosine(&direction);
+            let contribution = vertex.throughput.component_mul(&scattering) * importance * cosine
+                / (distance * distance);
+
+            if contribution.max() <= 0. || !scene.is_visible(&camera.position, &vertex.position) {
+                return none;
+            }
+
+            return (contribution, Some(camera));
         }
-        if total_importance > 0. {
-            total_light /= total_importance;
+
+        if s == 1 {
+            // Connect the camera path to a newly sampled point on a light.
+            let vertex = &camera_path[t - 1];
+            if vertex.delta {
+                return none;
+            }
+
+            let Some((light, probability)) = scene.sample_light(sampler.get_1d()) else {
+                return none;
+            };
+            if light.is_infinite() {
+                return Self::connect_infinite(scene, vertex, light, probability, sampler);
+            }
+            let Some(light_vertex) = PathVertex::sample_light(scene, light, probability, sampler)
+            else {
+                return none;
+            };
+
+            let difference = light_vertex.position - vertex.position;
+            let distance_squared = difference.magnitude_squared();
+            let direction = difference.normalize();
+
+            let geometry =
+                vertex.cosine(&direction) * light_vertex.cosine(&direction) / distance_squared;
+            let contribution = vertex
+                .throughput
+                .component_mul(&vertex.bsdf(&light_vertex, PathDirection::CameraPath))
+                .component_mul(&light_vertex.bsdf(vertex, PathDirection::LightPath))
+                .component_mul(&light_vertex.throughput)
+                * geometry;
+
+            if contribution.max() <= 0.
+                || !scene.is_visible(&vertex.position, &light_vertex.position)
+            {
+                return none;
+            }
+
+            return (contribution, Some(light_vertex));
         }
-        total_light
+
+        let light_vertex = &light_path[s - 1];
+        let camera_vertex = &camera_path[t - 1];
+        if light_vertex.delta || camera_vertex.delta {
+            return none;
+        }
+
+        let difference = light_vertex.position - camera_vertex.position;
+        let distance_squared = difference.magnitude_squared();
+        let direction = difference.normalize();
+        let geometry = camera_vertex.normal.dot(&direction).abs()
+            * light_vertex.normal.dot(&direction).abs()
+            / distance_squared;
+
+        let contribution = light_vertex
+            .throughput
+            .component_mul(&light_vertex.bsdf(camera_vertex, PathDirection::LightPath))
+            .component_mul(&camera_vertex.bsdf(light_vertex, PathDirection::CameraPath))
+            .component_mul(&camera_vertex.throughput)
+            * geometry;
+
+        if contribution.max() <= 0.
+            || !scene.is_visible(&camera_vertex.position, &light_vertex.position)
+        {
+            return none;
+        }
+
+        (contribution, None)
+    }
+
+    /// Connects `vertex` to a light infinitely far away, in a sampled direction. The returned
+    /// vertex only marks that direction. Directional lights without a size can't be hit and don't
+    /// start light paths, so this is the only strategy that finds them.
+    fn connect_infinite<'a>(
+        scene: &'a Scene,
+        vertex: &PathVertex<'a>,
+        light: &'a Light,
+        probability: f64,
+        sampler: &mut dyn Sampler,
+    ) -> (Vector3<f64>, Option<PathVertex<'a>>) {
+        let none = (Vector3::zeros(), None);
+
+        let Some(sample) = light.sample_incident(scene, &vertex.position, sampler) else {
+            return none;
+        };
+        let direction = sample.direction;
+
+        let point = ShadingPoint {
+            position: vertex.position + direction,
+            normal: Vector3::zeros(),
+            uv: Vector2::zeros(),
+        };
+        let mut light_vertex = PathVertex::light(light, &point, None, probability * sample.pdf);
+        if !sample.delta {
+            // The same direction could have been found by sampling any of the lights that rays
+            // leaving the scene see.
+            light_vertex.pdf_forward = scene.infinite_light_pdf(&direction);
+            light_vertex.emitter_pdf = light_vertex.pdf_forward;
+        }
+
+        let contribution = vertex
+            .throughput
+            .component_mul(&vertex.bsdf(&light_vertex, PathDirection::CameraPath))
+            .component_mul(&sample.radiance)
+            * vertex.cosine(&direction)
+            / (probability * sample.pdf);
+
+        let shadow_ray = Ray {
+            origin: vertex.position + direction * 0.001,
+            direction,
+        };
+        if contribution.max() <= 0. || scene.occluded(&shadow_ray, f64::INFINITY) {
+            return none;
+        }
+
+        (contribution, Some(light_vertex))
+    }
+
+    /// Multiple importance sampling weight of connecting `s` light vertices with `t` camera
+    /// vertices, using the power heuristic over all strategies that could create the same path.
+    fn mis_weight(
+        scene: &Scene,
+        light_path: &[PathVertex],
+        camera_path: &[PathVertex],
+        sampled: Option<PathVertex>,
+        s: usize,
+        t: usize,
+    ) -> f64 {
+        if sampled.is_some_and(
+            |vertex| matches!(vertex.light, Some(Light::Directional(light)) if light.is_delta()),
+        ) {
+            return 1.;
+        }
+
+        // The light path can be empty when the sampled light doesn't start light paths.
+        let mut light_path = light_path[..s.min(light_path.len())].to_vec();
+        let mut camera_path = camera_path[..t].to_vec();
+        if let Some(sampled) = sampled {
+            match sampled.kind {
+                VertexKind::Camera => camera_path[0] = sampled,
+                _ if light_path.is_empty() => light_path.push(sampled),
+                _ => light_path[0] = sampled,
+            }
+        }
+
+        // Update the reverse densities of the vertices around the connection.
+        let light_vertex = s.checked_sub(1).map(|i| light_path[i]);
+        let light_previous = s.checked_sub(2).map(|i| light_path[i]);
+        let camera_vertex = camera_path[t - 1];
+        let camera_previous = t.checked_sub(2).map(|i| camera_path[i]);
+
+        camera_path[t - 1].delta = false;
+        camera_path[t - 1].pdf_reverse = match &light_vertex {
+            Some(light_vertex) => light_vertex.pdf(scene, light_previous.as_ref(), &camera_vertex),
+            None => camera_vertex.emitter_pdf,
+        };
+
+        if let Some(camera_previous) = &camera_previous {
+            camera_path[t - 2].pdf_reverse = match &light_vertex {
+                Some(light_vertex) => camera_vertex.pdf(scene, Some(light_vertex), camera_previous),
+                None => camera_vertex.light_pdf(scene, camera_previous),
+            };
+        }
+
+        if let Some(light_vertex) = &light_vertex {
+            light_path[s - 1].delta = false;
+            light_path[s - 1].pdf_reverse =
+                camera_vertex.pdf(scene, camera_previous.as_ref(), light_vertex);
+
+            if let Some(light_previous) = &light_previous {
+                light_path[s - 2].pdf_reverse =
+                    light_vertex.pdf(scene, Some(&camera_vertex), light_previous);
+            }
+        }
+
+        let remap = |pdf: f64| if pdf == 0. { 1. } else { pdf };
+        let mut sum = 0.;
+
+        // Strategies that use fewer camera vertices.
+        let mut ratio = 1.;
+        for i in (1..t).rev() {
+            ratio *= remap(camera_path[i].pdf_reverse) / remap(camera_path[i].pdf_forward);
+            let connectible = if i > 1 {
+                true
+            } else {
+                scene.camera.is_pinhole() && !camera_path[1].is_infinite_light()
+            };
+            if connectible && !camera_path[i].delta && !camera_path[i - 1].delta {
+                sum += ratio * ratio;
+            }
+        }
+
+        // Strategies that use fewer light vertices.
+        let mut ratio = 1.;
+        for i in (0..s).rev() {
+            ratio *= remap(light_path[i].pdf_reverse) / remap(light_path[i].pdf_forward);
+            let previous_delta = if i > 0 {
+                light_path[i - 1].delta
+            } else {
+                light_path[0].is_delta_light()
+            };
+            if !light_path[i].delta && !previous_delta {
+                sum += ratio * ratio;
+            }
+        }
+
+        1. / (1. + sum)
     }
 }
 
 impl Renderer for BDPTRenderer {
-    fn render(&self, scene: &crate::Scene) -> RenderBuffer {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
         let width = scene.camera.width;
         let height = scene.camera.height;
+        let max_bounces = self.max_bounces as usize;
 
         let mut render_buffer = RenderBuffer::new(width, height);
 
         for x in 0..width {
             for y in 0..height {
-                let ray = scene.camera.get_ray(x, y);
+                sampler.start_pixel_sample((x, y), sample_index);
+                let camera_path = self.camera_path(scene, x, y, sampler);
+                let light_path = self.light_path(scene, sampler);
+
+                for t in 1..=camera_path.len() {
+                    // Connections to a sampled light don't need the light path.
+                    for s in 0..=light_path.len().max(1) {
+                        if s + t < 2 || s + t - 2 > max_bounces {
+                            continue;
+                        }
+
+                        let (contribution, sampled) =
+                            Self::connect(scene, &light_path, &camera_path, s, t, sampler);
+                        if contribution.max() <= 0. {
+                            continue;
+                        }
 
-                render_buffer[(x, y)] = self.sample_color(&ray, scene);
+                        let weight =
+                            Self::mis_weight(scene, &light_path, &camera_path, sampled, s, t);
+                        let contribution = contribution * weight;
+
+                        if t == 1 {
+                            // Light paths connected to the camera can land in any pixel, and are
+                            // spread over its neighbours by the filter.
+                            let light_position = light_path[s - 1].position;
+                            if let Some(position) = scene.camera.raster_position(&light_position) {
+                                for (pixel, weight) in scene.camera.pixel_weights(&position) {
+                                    render_buffer[pixel] += contribution * weight;
+                                }
+                            }
+                        } else {
+                            render_buffer[(x, y)] += contribution;
+                        }
+                    }
+                }
             }
         }
 
@@ -1,6 +1,6 @@
 use nalgebra::Vector3;
 
-use crate::{RenderBuffer, Renderer};
+use crate::{renderer::render_pixels, RenderBuffer, Renderer, Sampler, Scene};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DepthRenderMode {
@@ -20,41 +20,35 @@ impl DepthRenderer {
 }
 
 impl Renderer for DepthRenderer {
-    fn render(&self, scene: &crate::Scene) -> RenderBuffer {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
         let mut max_depth: f64 = 0.;
         let mut min_depth: f64 = scene.camera.perspective.zfar();
 
-        let mut buffer = vec![0.; scene.camera.width as usize * scene.camera.height as usize];
-        for x in 0..scene.camera.width {
-            for y in 0..scene.camera.height {
-                let ray = scene.camera.get_ray(x, y);
-
-                if let Some((_, intersection)) = scene.intersection(&ray) {
-                    buffer[y as usize * scene.camera.width as usize + x as usize] =
-                        intersection.distance;
-
-                    if intersection.distance > max_depth {
-                        max_depth = intersection.distance;
-                    }
-                    if intersection.distance < min_depth {
-                        min_depth = intersection.distance;
-                    }
-                }
-            }
-        }
+        let mut render_buffer = render_pixels(scene, sampler, sample_index, |(x, y), sampler| {
+            let ray = scene.camera.get_ray(x, y, sampler);
 
-        if self.depth_render_mode == DepthRenderMode::Normalized {
-            for x in &mut buffer {
-                *x = 1. - (*x - min_depth) / (max_depth - min_depth);
+            let Some((_, intersection)) = scene.intersection(&ray) else {
+                return Vector3::zeros();
+            };
+            if intersection.distance > max_depth {
+                max_depth = intersection.distance;
             }
-        }
-
-        let mut render_buffer = RenderBuffer::new(scene.camera.width, scene.camera.height);
-        for x in 0..scene.camera.width {
-            for y in 0..scene.camera.height {
-                let depth = buffer[y as usize * scene.camera.width as usize + x as usize];
-                render_buffer[(x, y)] = Vector3::new(depth, depth, depth);
+            if intersection.distance < min_depth {
+                min_depth = intersection.distance;
             }
+
+            let depth = intersection.distance;
+            Vector3::new(depth, depth, depth)
+        });
+
+        if self.depth_render_mode == DepthRenderMode::Normalized {
+            render_buffer =
+                render_buffer.map_float(|depth| 1. - (depth - min_depth) / (max_depth - min_depth));
         }
 
         render_buffer
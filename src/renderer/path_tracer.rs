@@ -0,0 +1,167 @@
+use crate::{
+    renderer::{russian_roulette, Integrator},
+    Material, Ray, Sampler, Scene, ShadingPoint,
+};
+
+use na::Vector3;
+use nalgebra as na;
+
+/// Power heuristic with an exponent of 2 for multiple importance sampling, returns the weight of
+/// the strategy with density `pdf` against the one with density `other_pdf`.
+pub(crate) fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let pdf = pdf * pdf;
+    let other_pdf = other_pdf * other_pdf;
+    if pdf + other_pdf > 0. {
+        pdf / (pdf + other_pdf)
+    } else {
+        0.
+    }
+}
+
+/// Unidirectional path tracer with next event estimation. At every bounce a point on a light is
+/// sampled, and light sampling is combined with BSDF sampling through multiple importance
+/// sampling. Paths scatter at most `max_bounces` times, and after `roulette_depth` bounces dim
+/// paths are ended early with Russian roulette.
+pub struct PathTracer {
+    pub max_bounces: u8,
+    pub roulette_depth: u8,
+}
+
+impl PathTracer {
+    pub fn new(max_bounces: u8) -> Self {
+        Self {
+            max_bounces,
+            roulette_depth: 3,
+        }
+    }
+
+    /// Sets the number of bounces before Russian roulette starts, 3 by default.
+    pub fn with_roulette_depth(mut self, roulette_depth: u8) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
+
+    /// Next event estimation: the contribution of a randomly sampled light, weighted against
+    /// finding that same light through BSDF sampling.
+    fn sample_light(
+        scene: &Scene,
+        material: &Material,
+        incoming: &Vector3<f64>,
+        point: &ShadingPoint,
+        sampler: &mut dyn Sampler,
+    ) -> Vector3<f64> {
+        let position = &point.position;
+        let normal = &point.normal;
+
+        let Some((light, selection_pdf)) = scene.sample_light(sampler.get_1d()) else {
+            return Vector3::zeros();
+        };
+        let Some(sample) = light.sample_incident(scene, position, sampler) else {
+            return Vector3::zeros();
+        };
+        let direction = sample.direction;
+
+        let bsdf = material.evaluate(incoming, &direction, point);
+        if bsdf.max() <= 0. {
+            return Vector3::zeros();
+        }
+
+        let shadow_ray = Ray {
+            origin: position + direction * 0.001,
+            direction,
+        };
+        if scene.occluded(&shadow_ray, sample.distance - 0.002) {
+            return Vector3::zeros();
+        }
+
+        let light_pdf = sample.pdf * selection_pdf;
+        let weight = if sample.delta {
+            1.
+        } else {
+            let scattering_pdf = material.scattering_pdf(incoming, &direction, normal);
+            power_heuristic(light_pdf, scattering_pdf)
+        };
+
+        let cosine = direction.dot(normal).abs();
+        bsdf.component_mul(&sample.radiance) * cosine * weight / light_pdf
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let mut color = Vector3::zeros();
+        let mut throughput = Vector3::new(1., 1., 1.);
+        let mut current_ray = *ray;
+
+        // The camera ray behaves like a specular bounce, light can't be sampled towards it.
+        let mut specular_bounce = true;
+        let mut scattering_pdf = 0.;
+
+        for bounce in 0..=self.max_bounces {
+            let Some((object, intersection)) = scene.intersection(&current_ray) else {
+                // The ray left the scene and sees the environment and lights like the sun.
+                let direction = current_ray.direction;
+                for (light, probability) in scene.infinite_lights() {
+                    let weight = if specular_bounce {
+                        1.
+                    } else {
+                        let light_pdf = probability * light.direction_pdf(&direction);
+                        power_heuristic(scattering_pdf, light_pdf)
+                    };
+                    color += throughput.component_mul(&light.radiance(&direction)) * weight;
+                }
+                break;
+            };
+            let material = object.material();
+            let incoming = current_ray.direction;
+            let position = intersection.position;
+            let normal = intersection.normal;
+            let point = ShadingPoint::from(&intersection);
+
+            let emission = material.emission(&point, &-incoming);
+            if emission.max() > 0. {
+                let weight = if specular_bounce {
+                    1.
+                } else {
+                    let cosine = normal.dot(&incoming).abs();
+                    let light_pdf = object.light_probability() * intersection.distance.powi(2)
+                        / (cosine * object.area());
+                    power_heuristic(scattering_pdf, light_pdf)
+                };
+                color += throughput.component_mul(&emission) * weight;
+            }
+
+            // Both light sampling and BSDF sampling would add another bounce.
+            if bounce == self.max_bounces {
+                break;
+            }
+
+            if !material.is_specular() {
+                color += throughput.component_mul(&Self::sample_light(
+                    scene, material, &incoming, &point, sampler,
+                ));
+            }
+
+            let Some(sample) = material.sample_scattering(&incoming, &point, sampler) else {
+                break;
+            };
+
+            throughput.component_mul_assign(&sample.weight);
+            if bounce >= self.roulette_depth {
+                let Some(survival) = russian_roulette(&throughput, sampler) else {
+                    break;
+                };
+                throughput /= survival;
+            }
+
+            specular_bounce = sample.specular;
+            scattering_pdf = sample.pdf;
+            current_ray = Ray {
+                origin: position + sample.direction * 0.001,
+                direction: sample.direction,
+            };
+        }
+
+        color
+    }
+}
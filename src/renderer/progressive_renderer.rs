@@ -0,0 +1,138 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{sampler::Sampler, RenderBuffer, Scene};
+
+use super::{default_sampler, Renderer};
+
+/// Renders pass after pass and keeps the average of all passes so far, which can be looked at
+/// while the image is still converging. A render stops after `max_passes`, once the time budget
+/// is used up or when the cancel flag is set. These are checked between passes, so a pass that
+/// has started is always finished.
+///
+/// Each pass is one [`Renderer::render_sample`] of the wrapped renderer, which can be a
+/// [`ParallelRenderer`](super::ParallelRenderer) or [`TileRenderer`](super::TileRenderer) to
+/// render several samples at once.
+pub struct ProgressiveRenderer<R: Renderer> {
+    renderer: R,
+    max_passes: usize,
+    sampler: Box<dyn Sampler>,
+    time_budget: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl<R: Renderer> ProgressiveRenderer<R> {
+    pub fn new(renderer: R, max_passes: usize) -> Self {
+        let sampler = default_sampler(max_passes * renderer.num_samples());
+        Self {
+            renderer,
+            max_passes,
+            sampler,
+            time_budget: None,
+            cancel: None,
+        }
+    }
+
+    /// Draws the samples from `sampler` instead of uniform random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// Seeds the sampler, which makes the render the same every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.sampler.set_seed(seed);
+        self
+    }
+
+    /// Stops starting new passes once `time_budget` has passed.
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    /// Stops starting new passes once `cancel` is set, for example from another thread.
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Renders the passes and calls `on_pass` after each of them with the average so far and
+    /// the number of samples per pixel it is made of. Returns the final average, which is black
+    /// if the render was cancelled before the first pass.
+    pub fn render_progressive<F>(&self, scene: &Scene, on_pass: F) -> RenderBuffer
+    where
+        F: FnMut(&RenderBuffer, usize),
+    {
+        let mut sampler = self.sampler.clone_box();
+        self.render_passes(scene, sampler.as_mut(), 0, on_pass)
+    }
+
+    fn render_passes<F>(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+        mut on_pass: F,
+    ) -> RenderBuffer
+    where
+        F: FnMut(&RenderBuffer, usize),
+    {
+        let start = Instant::now();
+        let first = sample_index * self.max_passes;
+
+        let mut sum = RenderBuffer::new(scene.camera.width, scene.camera.height);
+        let mut num_passes = 0;
+
+        while num_passes < self.max_passes && !self.is_stopped(start) {
+            sum += self
+                .renderer
+                .render_sample(scene, sampler, first + num_passes);
+            num_passes += 1;
+
+            let mut average = sum.clone();
+            average /= num_passes as f64;
+            on_pass(&average, num_passes * self.renderer.num_samples());
+        }
+
+        if num_passes > 0 {
+            sum /= num_passes as f64;
+        }
+        sum
+    }
+
+    fn is_stopped(&self, start: Instant) -> bool {
+        let cancelled = self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        let out_of_time = self
+            .time_budget
+            .is_some_and(|time_budget| start.elapsed() >= time_budget);
+        cancelled || out_of_time
+    }
+}
+
+impl<R: Renderer> Renderer for ProgressiveRenderer<R> {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        self.render_passes(scene, sampler, sample_index, |_, _| {})
+    }
+
+    fn num_samples(&self) -> usize {
+        self.max_passes * self.renderer.num_samples()
+    }
+
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        self.render_progressive(scene, |_, _| {})
+    }
+}
@@ -1,163 +1,389 @@
+use std::iter;
+
 use na::{Point3, Vector3};
 use nalgebra as na;
 
-use crate::{Material, Ray, RenderBuffer, Renderer, Scene};
+use crate::{
+    renderer::{russian_roulette, Integrator},
+    Light, Material, Ray, Sampler, Scene, ShadingPoint,
+};
 
 #[derive(Clone, Copy)]
 struct PathVertex<'a> {
     pub position: Point3<f64>,
     pub normal: Vector3<f64>,
+    pub point: ShadingPoint,
+    /// Direction of the ray that arrived at this vertex.
     pub incoming: Vector3<f64>,
     pub material: &'a Material,
+    /// The light arriving at this vertex divided by the density of the path so far.
     pub accumulated_emission: Vector3<f64>,
+    /// Whether the light path went on through specular scattering, which can't be connected to.
+    pub specular: bool,
+}
+
+/// The surface vertices of a light path, along with how the light it started from can be found.
+struct LightPath<'a> {
+    vertices: Vec<PathVertex<'a>>,
+    hittable: bool,
+}
+
+/// Whether camera paths can find `light` by hitting it.
+fn is_hittable(scene: &Scene, light: &Light) -> bool {
+    matches!(light, Light::Area(_))
+        || scene
+            .infinite_lights()
+            .any(|(infinite_light, _)| std::ptr::eq(infinite_light, light))
 }
 
+/// Number of strategies that can produce a path, given whether each of its vertices between the
+/// camera and the light can be connected to, in order from the camera. Every pair of
+/// neighbouring vertices that can both be connected to is a connection of a camera path to a
+/// light path, and the last vertex can be connected to a sampled point on the light. On top of
+/// that camera paths can hit the light if it is `hittable`, and the other strategies need the
+/// light to be `selectable` by [`Scene::sample_light`].
+fn strategy_count(
+    connectable: impl Iterator<Item = bool>,
+    hittable: bool,
+    selectable: bool,
+) -> f64 {
+    let mut count = hittable as usize;
+    if selectable {
+        let mut previous = None;
+        for connectable in connectable {
+            if previous == Some(true) && connectable {
+                count += 1;
+            }
+            previous = Some(connectable);
+        }
+        if previous == Some(true) {
+            count += 1;
+        }
+    }
+    count as f64
+}
+
+/// Connects every vertex of a camera path to every vertex of a light path and to a point
+/// sampled on a light. Paths scatter at most `max_bounces` times in total, like with
+/// [`PathTracer`](crate::renderer::PathTracer). Every strategy that could have produced a path
+/// gets an equal share of it, which only depends on which vertices of the path can be connected
+/// to, so after `roulette_depth` bounces dim camera and light paths are both ended early with
+/// Russian roulette.
 pub struct RecursiveBDPT {
     max_bounces: u8,
+    roulette_depth: u8,
 }
 
 impl RecursiveBDPT {
     pub fn new(max_bounces: u8) -> Self {
-        Self { max_bounces }
+        Self {
+            max_bounces,
+            roulette_depth: 3,
+        }
     }
 
-    fn sample_light_path<'a>(
-        &'a self,
-        ray: &'a Ray,
-        scene: &'a Scene,
-        material: &'a Material,
-    ) -> Vec<PathVertex<'a>> {
-        let emission = material.emission_color(); // / light_area;
-
-        let mut current_path = vec![PathVertex {
-            position: ray.origin,
-            normal: ray.direction,
-            incoming: -ray.direction,
-            material,
-            accumulated_emission: emission,
-        }];
-
-        let mut current_ray = *ray;
-        let mut accumulated_emission = emission;
-        for _bounce in 0..self.max_bounces {
-            if let Some((object, intersection)) = scene.intersection(&current_ray) {
-                let material = object.material();
-                let interaction = material.interact(&current_ray, &intersection);
-
-                accumulated_emission = (accumulated_emission.component_mul(&interaction.filter)
-                    + interaction.emission)
-                    * interaction.pdf;
-
-                let vertex = PathVertex {
-                    position: interaction.position,
-                    normal: interaction.surface_normal,
-                    incoming: current_ray.direction,
-                    material,
-                    accumulated_emission,
-                };
+    /// Sets the number of bounces before Russian roulette starts, 3 by default.
+    pub fn with_roulette_depth(mut self, roulette_depth: u8) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
 
-                current_path.push(vertex);
-                if let Some(outgoing) = interaction.outgoing {
-                    current_ray = outgoing;
-                } else {
-                    break;
+    fn sample_light_path<'a>(&self, scene: &'a Scene, sampler: &mut dyn Sampler) -> LightPath<'a> {
+        let mut path = LightPath {
+            vertices: Vec::new(),
+            hittable: false,
+        };
+        let Some((light, probability)) = scene.sample_light(sampler.get_1d()) else {
+            return path;
+        };
+        path.hittable = is_hittable(scene, light);
+
+        let (ray, emission) = match light {
+            Light::Area(index) => {
+                let object = &scene.objects[*index];
+                let (point, ray) = object.sample_emissive_ray(sampler);
+                let pdf = object.material().emitter().map_or(0., |emitter| {
+                    emitter.direction_pdf(&point.normal, &ray.direction)
+                }) / object.area();
+                if pdf == 0. {
+                    return path;
                 }
-            } else {
+                let emission = object.material().emission(&point, &ray.direction)
+                    * ray.direction.dot(&point.normal).abs()
+                    / pdf;
+                (ray, emission)
+            }
+            _ => {
+                let Some((ray, emission, pdf)) = light.sample_emission(scene, sampler) else {
+                    return path;
+                };
+                if pdf == 0. {
+                    return path;
+                }
+                (ray, emission / pdf)
+            }
+        };
+
+        let mut current_ray = ray;
+        let mut accumulated_emission = emission / probability;
+        // Russian roulette looks at how much of the emitted light is left.
+        let mut attenuation = Vector3::new(1., 1., 1.);
+        for bounce in 0..self.max_bounces {
+            let Some((object, intersection)) = scene.intersection(&current_ray) else {
+                break;
+            };
+            let material = object.material();
+            let incoming = current_ray.direction;
+            let point = ShadingPoint::from(&intersection);
+
+            let sample = material.sample_scattering(&incoming, &point, sampler);
+            path.vertices.push(PathVertex {
+                position: intersection.position,
+                normal: intersection.normal,
+                point,
+                incoming,
+                material,
+                accumulated_emission,
+                specular: sample.is_some_and(|sample| sample.specular),
+            });
+            let Some(sample) = sample else {
                 break;
+            };
+
+            let weight = if sample.specular {
+                // Importance is not scaled when crossing into a different medium.
+                sample.weight * sample.eta * sample.eta
+            } else {
+                // Light paths carry importance, which scatters with the adjoint BSDF.
+                let adjoint = material.evaluate(&-sample.direction, &-incoming, &point);
+                adjoint * sample.direction.dot(&intersection.normal).abs() / sample.pdf
+            };
+            accumulated_emission.component_mul_assign(&weight);
+            attenuation.component_mul_assign(&weight);
+
+            if bounce >= self.roulette_depth {
+                let Some(survival) = russian_roulette(&attenuation, sampler) else {
+                    break;
+                };
+                accumulated_emission /= survival;
+                attenuation /= survival;
             }
+
+            current_ray = Ray {
+                origin: intersection.position + sample.direction * 0.001,
+                direction: sample.direction,
+            };
         }
 
-        current_path
+        path
     }
 
-    fn sample_camera_path(
-        ray: &Ray,
+    /// The light reaching `vertex` from a point sampled on a light, for the path that ends
+    /// there. `connectable` tells which camera vertices before `vertex` can be connected to.
+    fn sample_light(
         scene: &Scene,
-        light_path: &[PathVertex],
-        bounces_left: u8,
+        vertex: &PathVertex,
+        connectable: &[bool],
+        sampler: &mut dyn Sampler,
     ) -> Vector3<f64> {
-        if bounces_left == 0 {
+        let Some((light, probability)) = scene.sample_light(sampler.get_1d()) else {
+            return Vector3::zeros();
+        };
+        let Some(sample) = light.sample_incident(scene, &vertex.position, sampler) else {
+            return Vector3::zeros();
+        };
+        let direction = sample.direction;
+
+        let bsdf = vertex
+            .material
+            .evaluate(&vertex.incoming, &direction, &vertex.point);
+        if bsdf.max() <= 0. {
             return Vector3::zeros();
         }
-        if let Some((object, intersection)) = scene.intersection(ray) {
-            let current_position = &intersection.position;
-            let current_normal = &intersection.normal;
-            let material = object.material();
 
-            let mut current_color = Vector3::zeros();
-            let mut total_likelihood = 0.;
-
-            let interaction = material.interact(ray, &intersection);
-            if let Some(outgoing) = &interaction.outgoing {
-                let backward_path_color =
-                    Self::sample_camera_path(outgoing, scene, light_path, bounces_left - 1);
-
-                current_color += backward_path_color * interaction.pdf;
-                total_likelihood += interaction.pdf;
-
-                for vertex_light in light_path {
-                    if scene.is_visible(current_position, &vertex_light.position) {
-                        let light_color = vertex_light.accumulated_emission;
-
-                        let light_to_camera_connection =
-                            (current_position - vertex_light.position).normalize();
-
-                        let light_importance = vertex_light.material.likelihood(
-                            &vertex_light.incoming,
-                            &light_to_camera_connection,
-                            &vertex_light.normal,
-                        );
-
-                        if light_importance > 0. {
-                            let ray_importance = material.likelihood(
-                                &light_to_camera_connection,
-                                &-ray.direction,
-                                current_normal,
-                            );
-
-                            current_color += light_color * ray_importance * light_importance;
-                            total_likelihood += ray_importance;
-                        }
-                    }
-                }
-            }
-            if total_likelihood > 0. {
-                current_color /= total_likelihood;
-            }
-            current_color
-                .component_mul_assign(&material.absorption_color(&current_position.coords));
-            current_color += material.emission_color(); // / object.area();
-            current_color
-        } else {
-            Vector3::zeros()
+        let shadow_ray = Ray {
+            origin: vertex.position + direction * 0.001,
+            direction,
+        };
+        if scene.occluded(&shadow_ray, sample.distance - 0.002) {
+            return Vector3::zeros();
         }
+
+        let strategies = strategy_count(
+            connectable.iter().copied().chain(iter::once(true)),
+            is_hittable(scene, light),
+            true,
+        );
+        bsdf.component_mul(&sample.radiance) * direction.dot(&vertex.normal).abs()
+            / (sample.pdf * probability * strategies)
     }
 
-    fn sample_color(&self, ray: &Ray, scene: &Scene) -> Vector3<f64> {
-        let light = scene.random_light();
+    /// The light that `light_vertex` sends to `camera_vertex`, without the throughput of the
+    /// camera path.
+    fn connect(
+        scene: &Scene,
+        camera_vertex: &PathVertex,
+        light_vertex: &PathVertex,
+    ) -> Vector3<f64> {
+        if light_vertex.material.is_specular() {
+            return Vector3::zeros();
+        }
 
-        let light_ray = light.sample_emissive_ray();
-        let light_path = self.sample_light_path(&light_ray, scene, light.material());
+        let difference = light_vertex.position - camera_vertex.position;
+        let distance_squared = difference.magnitude_squared();
+        let direction = difference.normalize();
+
+        let camera_bsdf = camera_vertex.material.evaluate(
+            &camera_vertex.incoming,
+            &direction,
+            &camera_vertex.point,
+        );
+        let light_bsdf = light_vertex.material.evaluate(
+            &direction,
+            &-light_vertex.incoming,
+            &light_vertex.point,
+        );
+        let geometry = direction.dot(&camera_vertex.normal).abs()
+            * direction.dot(&light_vertex.normal).abs()
+            / distance_squared;
+        if camera_bsdf.max() <= 0. || light_bsdf.max() <= 0. || geometry <= 0. {
+            return Vector3::zeros();
+        }
+        if !scene.is_visible(&camera_vertex.position, &light_vertex.position) {
+            return Vector3::zeros();
+        }
 
-        Self::sample_camera_path(ray, scene, &light_path, self.max_bounces)
+        camera_bsdf
+            .component_mul(&light_bsdf)
+            .component_mul(&light_vertex.accumulated_emission)
+            * geometry
     }
-}
 
-impl Renderer for RecursiveBDPT {
-    fn render(&self, scene: &crate::Scene) -> RenderBuffer {
-        let width = scene.camera.width;
-        let height = scene.camera.height;
+    /// The light arriving along `ray`, where `throughput` is the contribution of the camera path
+    /// so far divided by its density and `connectable` tells which of its vertices can be
+    /// connected to.
+    fn sample_camera_path(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        light_path: &LightPath,
+        throughput: Vector3<f64>,
+        connectable: &mut Vec<bool>,
+        sampler: &mut dyn Sampler,
+    ) -> Vector3<f64> {
+        let Some((object, intersection)) = scene.intersection(ray) else {
+            // The ray left the scene and sees the environment and lights like the sun.
+            return scene
+                .infinite_lights()
+                .map(|(light, probability)| {
+                    let strategies =
+                        strategy_count(connectable.iter().copied(), true, probability > 0.);
+                    throughput.component_mul(&light.radiance(&ray.direction)) / strategies
+                })
+                .sum();
+        };
+        let material = object.material();
+        let vertex = PathVertex {
+            position: intersection.position,
+            normal: intersection.normal,
+            point: ShadingPoint::from(&intersection),
+            incoming: ray.direction,
+            material,
+            accumulated_emission: Vector3::zeros(),
+            specular: false,
+        };
 
-        let mut render_buffer = RenderBuffer::new(width, height);
+        let mut color = Vector3::zeros();
+        let emission = material.emission(&vertex.point, &-ray.direction);
+        if emission.max() > 0. {
+            let strategies = strategy_count(
+                connectable.iter().copied(),
+                true,
+                object.light_probability() > 0.,
+            );
+            color += throughput.component_mul(&emission) / strategies;
+        }
 
-        for x in 0..width {
-            for y in 0..height {
-                let ray = scene.camera.get_ray(x, y);
+        // Every strategy below adds another bounce.
+        let bounce = connectable.len();
+        if bounce == self.max_bounces as usize {
+            return color;
+        }
 
-                render_buffer[(x, y)] = self.sample_color(&ray, scene);
+        if !material.is_specular() {
+            color +=
+                throughput.component_mul(&Self::sample_light(scene, &vertex, connectable, sampler));
+
+            // Connections to deeper light vertices would make the path too long.
+            let max_light_vertices = self.max_bounces as usize - bounce - 1;
+            for (i, light_vertex) in light_path
+                .vertices
+                .iter()
+                .take(max_light_vertices)
+                .enumerate()
+            {
+                let contribution = Self::connect(scene, &vertex, light_vertex);
+                if contribution.max() <= 0. {
+                    continue;
+                }
+
+                let light_connectable = light_path.vertices[..i]
+                    .iter()
+                    .rev()
+                    .map(|light_vertex| !light_vertex.specular);
+                let strategies = strategy_count(
+                    connectable
+                        .iter()
+                        .copied()
+                        .chain([true, true])
+                        .chain(light_connectable),
+                    light_path.hittable,
+                    true,
+                );
+                color += throughput.component_mul(&contribution) / strategies;
             }
         }
 
-        render_buffer
+        let Some(sample) = material.sample_scattering(&ray.direction, &vertex.point, sampler)
+        else {
+            return color;
+        };
+        let mut throughput = throughput.component_mul(&sample.weight);
+        if bounce >= self.roulette_depth as usize {
+            let Some(survival) = russian_roulette(&throughput, sampler) else {
+                return color;
+            };
+            throughput /= survival;
+        }
+
+        let outgoing = Ray {
+            origin: intersection.position + sample.direction * 0.001,
+            direction: sample.direction,
+        };
+        connectable.push(!sample.specular);
+        color += self.sample_camera_path(
+            &outgoing,
+            scene,
+            light_path,
+            throughput,
+            connectable,
+            sampler,
+        );
+        connectable.pop();
+        color
+    }
+}
+
+impl Integrator for RecursiveBDPT {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let light_path = self.sample_light_path(scene, sampler);
+        self.sample_camera_path(
+            ray,
+            scene,
+            &light_path,
+            Vector3::new(1., 1., 1.),
+            &mut Vec::with_capacity(self.max_bounces as usize + 1),
+            sampler,
+        )
     }
 }
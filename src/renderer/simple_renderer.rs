@@ -1,4 +1,4 @@
-use crate::{reflect, RenderBuffer, Renderer, Scene};
+use crate::{reflect, renderer::Integrator, Ray, Sampler, Scene};
 
 use nalgebra as na;
 
@@ -6,34 +6,23 @@ use na::Vector3;
 
 pub struct SimpleRenderer;
 
-impl Renderer for SimpleRenderer {
-    fn render(&self, scene: &Scene) -> RenderBuffer {
-        let width = scene.camera.width;
-        let height = scene.camera.height;
+impl Integrator for SimpleRenderer {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let intersection = scene.intersection(ray);
 
-        let mut render_buffer = RenderBuffer::new(width, height);
+        if let Some((_object, intersection)) = intersection {
+            let reflection = reflect(&ray.direction, &intersection.normal);
 
-        for x in 0..width {
-            for y in 0..height {
-                let ray = scene.camera.get_ray(x, y);
+            let light_direction =
+                (Vector3::new(0., 0., -5.) - intersection.position.coords).normalize();
 
-                let intersection = scene.intersection(&ray);
+            let angle = reflection.dot(&light_direction);
 
-                if let Some((_object, intersection)) = intersection {
-                    let reflection = reflect(&ray.direction, &intersection.normal);
+            let lightness = angle.max(0.);
 
-                    let light_direction =
-                        (Vector3::new(0., 0., -5.) - intersection.position.coords).normalize();
-
-                    let angle = reflection.dot(&light_direction);
-
-                    let lightness = angle.max(0.);
-
-                    render_buffer[(x, y)] = Vector3::new(lightness, lightness, lightness);
-                }
-            }
+            Vector3::new(lightness, lightness, lightness)
+        } else {
+            Vector3::zeros()
         }
-
-        render_buffer
     }
 }
@@ -0,0 +1,217 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use nalgebra::Vector3;
+
+use crate::{sampler::Sampler, RenderBuffer, Scene};
+
+use super::{default_sampler, PixelRenderer, Renderer};
+
+/// A rectangle of pixels that is rendered as a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Covers an image with tiles of `size` pixels, row by row. Tiles at the right and bottom
+    /// edges are cut off.
+    pub fn split(width: u32, height: u32, size: u32) -> Vec<Tile> {
+        let size = size.max(1);
+        (0..height)
+            .step_by(size as usize)
+            .flat_map(|y| {
+                (0..width).step_by(size as usize).map(move |x| Tile {
+                    x,
+                    y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                })
+            })
+            .collect()
+    }
+
+    /// The pixels of the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let Tile {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+}
+
+/// Hands out the tiles of an image to threads that take them from a shared queue. The threads
+/// compute every pixel of a tile with `render_pixel` and pass the values of the finished tile,
+/// row by row, to `on_tile`. Each thread works with its own copy of `sampler`.
+pub(crate) fn render_in_tiles<T, P, F>(
+    (width, height): (u32, u32),
+    tile_size: u32,
+    num_threads: Option<usize>,
+    sampler: &dyn Sampler,
+    render_pixel: P,
+    on_tile: F,
+) where
+    P: Fn((u32, u32), &mut dyn Sampler) -> T + Sync,
+    F: Fn(&Tile, Vec<T>) + Sync,
+{
+    let tiles = Tile::split(width, height, tile_size);
+    let num_threads = num_threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from))
+        .min(tiles.len());
+
+    let next_tile = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..num_threads {
+            let mut sampler = sampler.clone_box();
+            let (tiles, next_tile) = (&tiles, &next_tile);
+            let (render_pixel, on_tile) = (&render_pixel, &on_tile);
+            s.spawn(move || {
+                while let Some(tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed)) {
+                    let values = tile
+                        .pixels()
+                        .map(|pixel| render_pixel(pixel, sampler.as_mut()))
+                        .collect();
+                    on_tile(tile, values);
+                }
+            });
+        }
+    });
+}
+
+/// Renders the image in tiles on several threads. Threads take the next tile from a shared
+/// queue, render all samples of its pixels and write it into the image, so memory doesn't grow
+/// with the number of threads and finished tiles can be shown while the rest is rendering.
+///
+/// Each pixel adds up its samples in order, with a seeded sampler the image is the same as that
+/// of an [`IterativeRenderer`](super::IterativeRenderer) for any number of threads.
+pub struct TileRenderer<R: PixelRenderer> {
+    renderer: R,
+    num_samples: usize,
+    sampler: Box<dyn Sampler>,
+    tile_size: u32,
+    num_threads: Option<usize>,
+}
+
+impl<R: PixelRenderer> TileRenderer<R> {
+    pub fn new(renderer: R, num_samples: usize) -> Self {
+        Self {
+            renderer,
+            num_samples,
+            sampler: default_sampler(num_samples),
+            tile_size: 32,
+            num_threads: None,
+        }
+    }
+
+    /// Draws the samples from `sampler` instead of uniform random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// Seeds the sampler, which makes the render the same every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.sampler.set_seed(seed);
+        self
+    }
+
+    /// Sets the width and height of the tiles in pixels, 32 by default.
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// Limits the number of threads, which defaults to the available parallelism.
+    pub fn with_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = Some(num_threads.max(1));
+        self
+    }
+
+    /// Renders the image and calls `on_tile` with the image so far each time a tile is done.
+    /// Pixels of tiles that aren't done yet are black.
+    pub fn render_tiles<F>(&self, scene: &Scene, on_tile: F) -> RenderBuffer
+    where
+        F: Fn(&Tile, &RenderBuffer) + Sync,
+    {
+        let mut sampler = self.sampler.clone_box();
+        self.render_tiles_with(scene, sampler.as_mut(), 0, &on_tile)
+    }
+
+    fn render_tiles_with(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+        on_tile: &(dyn Fn(&Tile, &RenderBuffer) + Sync),
+    ) -> RenderBuffer {
+        let width = scene.camera.width;
+        let height = scene.camera.height;
+
+        let first = sample_index * self.num_samples;
+        let render_buffer = Mutex::new(RenderBuffer::new(width, height));
+
+        render_in_tiles(
+            (width, height),
+            self.tile_size,
+            self.num_threads,
+            sampler,
+            |pixel, sampler| self.render_pixel(scene, pixel, sampler, first),
+            |tile, colors| {
+                let mut render_buffer = render_buffer.lock().unwrap();
+                for (pixel, color) in tile.pixels().zip(colors) {
+                    render_buffer[pixel] = color;
+                }
+                on_tile(tile, &render_buffer);
+            },
+        );
+
+        render_buffer.into_inner().unwrap()
+    }
+
+    /// The average of the samples of a pixel, starting at sample index `first`.
+    fn render_pixel(
+        &self,
+        scene: &Scene,
+        pixel: (u32, u32),
+        sampler: &mut dyn Sampler,
+        first: usize,
+    ) -> Vector3<f64> {
+        let mut color = Vector3::zeros();
+        for index in first..first + self.num_samples {
+            sampler.start_pixel_sample(pixel, index);
+            color += self.renderer.render_pixel(scene, pixel, sampler);
+        }
+        color.map(|x| x / self.num_samples as f64)
+    }
+}
+
+impl<R: PixelRenderer> Renderer for TileRenderer<R> {
+    fn render_sample(
+        &self,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        sample_index: usize,
+    ) -> RenderBuffer {
+        self.render_tiles_with(scene, sampler, sample_index, &|_, _| {})
+    }
+
+    fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    fn render(&self, scene: &Scene) -> RenderBuffer {
+        self.render_tiles(scene, |_, _| {})
+    }
+}
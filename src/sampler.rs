@@ -0,0 +1,139 @@
+use nalgebra::Vector2;
+
+mod blue_noise;
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+pub use blue_noise::BlueNoiseSampler;
+pub use halton::HaltonSampler;
+pub use independent::IndependentSampler;
+pub use sobol::SobolSampler;
+pub use stratified::StratifiedSampler;
+
+/// The largest `f64` below 1, samples are clamped to it to stay in [0, 1).
+const ONE_MINUS_EPSILON: f64 = 1. - f64::EPSILON / 2.;
+
+/// Provides the random numbers for rendering. Each sample of a pixel draws a series of
+/// dimensions from the sampler, and samplers other than the independent one place the samples
+/// of a pixel so that they cover every dimension evenly, which converges faster than uniform
+/// random numbers.
+///
+/// Samples only depend on the pixel, the sample index and the seed, not on the order in which
+/// they are rendered. Indices past the samples per pixel start over with a different pattern.
+pub trait Sampler: Send + Sync {
+    /// The number of samples per pixel the sampler was set up for.
+    fn samples_per_pixel(&self) -> usize;
+
+    /// The seed that picks the random numbers, or the scrambling of the sample patterns.
+    fn seed(&self) -> u64;
+
+    /// Changes the seed, samplers of the same kind and seed always produce the same samples.
+    fn set_seed(&mut self, seed: u64);
+
+    /// Starts the sample with the given index for a pixel, the next dimension is the first.
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize);
+
+    /// The next dimension, a number in [0, 1).
+    fn get_1d(&mut self) -> f64;
+
+    /// The next two dimensions, a point in [0, 1)². The first 2D sample of a pixel sample is
+    /// the position within the pixel.
+    fn get_2d(&mut self) -> Vector2<f64>;
+
+    /// A copy of this sampler for rendering on another thread.
+    fn clone_box(&self) -> Box<dyn Sampler>;
+}
+
+/// Scrambles the bits of `v`, a finalizer for hash functions.
+pub(crate) fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+/// Hashes the values into a single seed.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |state, value| {
+        mix_bits(state ^ value.wrapping_add(0x9e3779b97f4a7c15))
+    })
+}
+
+/// Element `i` of a random permutation of `0..length` selected by `seed`, after Kensler,
+/// "Correlated Multi-Jittered Sampling".
+pub(crate) fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut w = length.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    (i.wrapping_add(seed)) % length
+}
+
+/// Nested uniform scrambling of the bits of `v`, which keeps the stratification of
+/// low-discrepancy points while randomizing them.
+pub(crate) fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+    if seed & 1 != 0 {
+        v ^= 1 << 31;
+    }
+    for bit in 1..32 {
+        let mask = u32::MAX << (32 - bit);
+        if (mix_bits(((v & mask) ^ seed) as u64) as u32) & (1 << bit) != 0 {
+            v ^= 1 << (31 - bit);
+        }
+    }
+    v
+}
+
+/// A fraction from the bits of a 32 bit integer.
+pub(crate) fn fraction(bits: u32) -> f64 {
+    (bits as f64 / (1u64 << 32) as f64).min(ONE_MINUS_EPSILON)
+}
+
+/// A small, fast random number generator for samplers that need uniform random numbers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SplitMix(u64);
+
+impl SplitMix {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        mix_bits(self.0)
+    }
+
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
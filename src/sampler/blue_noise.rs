@@ -0,0 +1,194 @@
+use std::sync::OnceLock;
+
+use nalgebra::Vector2;
+
+use super::{
+    fraction, hash, owen_scramble, permutation_element,
+    sobol::{sobol_0, sobol_1},
+    Sampler, SplitMix, ONE_MINUS_EPSILON,
+};
+
+/// Width and height of the tiled blue noise mask.
+const MASK_SIZE: usize = 64;
+
+/// A blue noise mask, values in [0, 1) whose neighbors differ as much as possible, made with
+/// the void-and-cluster method of Ulichney.
+fn blue_noise_mask() -> &'static [f64] {
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    MASK.get_or_init(|| void_and_cluster(MASK_SIZE, 1.5))
+}
+
+/// Adds or removes a point at `index` to the energy, a Gaussian of `sigma` around every point
+/// on a torus.
+fn splat(energy: &mut [f64], size: usize, index: usize, sigma: f64, sign: f64) {
+    let radius = (3. * sigma).ceil() as isize;
+    let (x, y) = ((index % size) as isize, (index / size) as isize);
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let weight = (-((dx * dx + dy * dy) as f64) / (2. * sigma * sigma)).exp();
+            let nx = (x + dx).rem_euclid(size as isize) as usize;
+            let ny = (y + dy).rem_euclid(size as isize) as usize;
+            energy[ny * size + nx] += sign * weight;
+        }
+    }
+}
+
+/// The point with the highest energy among the set points, the tightest cluster, or the lowest
+/// energy among the unset points, the largest void.
+fn extreme(energy: &[f64], pattern: &[bool], set: bool) -> usize {
+    let candidates = (0..energy.len()).filter(|i| pattern[*i] == set);
+    if set {
+        candidates.max_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+    } else {
+        candidates.min_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+    }
+    .unwrap()
+}
+
+fn void_and_cluster(size: usize, sigma: f64) -> Vec<f64> {
+    let count = size * size;
+    let mut rng = SplitMix::new(0);
+
+    // Start from random points and spread them out by moving points from the tightest cluster
+    // into the largest void.
+    let mut pattern = vec![false; count];
+    let mut energy = vec![0.; count];
+    let initial_count = count / 10;
+    let mut placed = 0;
+    while placed < initial_count {
+        let index = (rng.next_u64() % count as u64) as usize;
+        if !pattern[index] {
+            pattern[index] = true;
+            splat(&mut energy, size, index, sigma, 1.);
+            placed += 1;
+        }
+    }
+    loop {
+        let cluster = extreme(&energy, &pattern, true);
+        pattern[cluster] = false;
+        splat(&mut energy, size, cluster, sigma, -1.);
+
+        let void = extreme(&energy, &pattern, false);
+        pattern[void] = true;
+        splat(&mut energy, size, void, sigma, 1.);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+
+    // Rank the initial points by removing them from the tightest clusters first.
+    let mut removal_pattern = pattern.clone();
+    let mut removal_energy = energy.clone();
+    for rank in (0..initial_count).rev() {
+        let cluster = extreme(&removal_energy, &removal_pattern, true);
+        removal_pattern[cluster] = false;
+        splat(&mut removal_energy, size, cluster, sigma, -1.);
+        ranks[cluster] = rank;
+    }
+
+    // Then fill the largest voids until every point is ranked. Past half of the points this is
+    // the same as removing the tightest clusters of the remaining unset points.
+    for rank in initial_count..count {
+        let void = extreme(&energy, &pattern, false);
+        pattern[void] = true;
+        splat(&mut energy, size, void, sigma, 1.);
+        ranks[void] = rank;
+    }
+
+    ranks
+        .into_iter()
+        .map(|rank| (rank as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// Gives every pixel the same Owen scrambled Sobol points, shifted by a blue noise mask. The
+/// error then varies between neighboring pixels as blue noise, which looks smoother than white
+/// noise at low sample counts. The mask is moved around for every dimension so dimensions don't
+/// correlate.
+#[derive(Debug, Clone)]
+pub struct BlueNoiseSampler {
+    samples_per_pixel: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.next_power_of_two(),
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// The shuffled index of the current sample, the scramble seed for the next dimension and
+    /// the seed for its offset into the mask. Unlike the other seeds these don't depend on the
+    /// pixel.
+    fn next_index(&mut self) -> (u32, u64, u64) {
+        let count = self.samples_per_pixel;
+        let round = self.sample_index / count;
+        let seed = hash(&[round as u64, self.dimension, self.seed]);
+        self.dimension += 1;
+
+        let index = permutation_element(
+            (self.sample_index % count) as u32,
+            count as u32,
+            seed as u32,
+        );
+        (index, seed, hash(&[seed]))
+    }
+
+    /// The mask value for the current pixel, with the mask shifted by an offset from `seed`.
+    fn shift(&self, seed: u64) -> f64 {
+        let mask = blue_noise_mask();
+        let x = (self.pixel.0 as u64 + seed) as usize % MASK_SIZE;
+        let y = (self.pixel.1 as u64 + (seed >> 32)) as usize % MASK_SIZE;
+        mask[y * MASK_SIZE + x]
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, seed, shift_seed) = self.next_index();
+        let x = fraction(owen_scramble(sobol_0(index), seed as u32));
+        (x + self.shift(shift_seed)).fract().min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> Vector2<f64> {
+        let (index, seed, shift_seed) = self.next_index();
+        let point = Vector2::new(
+            fraction(owen_scramble(sobol_0(index), seed as u32)),
+            fraction(owen_scramble(sobol_1(index), (seed >> 32) as u32)),
+        );
+        let shift = Vector2::new(self.shift(shift_seed), self.shift(hash(&[shift_seed, 1])));
+        (point + shift).map(|x| x.fract().min(ONE_MINUS_EPSILON))
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
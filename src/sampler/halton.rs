@@ -0,0 +1,105 @@
+use nalgebra::Vector2;
+
+use super::{hash, mix_bits, permutation_element, Sampler, SplitMix, ONE_MINUS_EPSILON};
+
+/// Bases of the dimensions, dimensions past the last one fall back to uniform random numbers.
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence, where dimension `d` is the radical inverse of the sample index in the
+/// `d`-th prime base. The digits are Owen scrambled with a different seed for every pixel, which
+/// decorrelates the pixels while keeping the stratification of each.
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    samples_per_pixel: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn next_dimension(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            dimension as u64,
+            self.seed,
+        ]);
+        match PRIMES.get(dimension) {
+            Some(base) => owen_scrambled_radical_inverse(*base, self.sample_index as u64, seed),
+            None => SplitMix::new(hash(&[seed, self.sample_index as u64])).next_f64(),
+        }
+    }
+}
+
+/// The digits of `index` in `base` mirrored around the decimal point, with every digit
+/// permuted depending on the digits before it.
+fn owen_scrambled_radical_inverse(base: u64, mut index: u64, seed: u64) -> f64 {
+    let inverse_base = 1. / base as f64;
+    let mut inverse_base_power = 1.;
+    let mut reversed_digits = 0u64;
+
+    // Continue until the digits no longer change the result.
+    while 1. - inverse_base_power < 1. {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_seed = mix_bits(seed ^ reversed_digits) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_seed) as u64;
+
+        reversed_digits = reversed_digits * base + digit;
+        inverse_base_power *= inverse_base;
+        index = next;
+    }
+
+    (reversed_digits as f64 * inverse_base_power).min(ONE_MINUS_EPSILON)
+}
+
+impl Sampler for HaltonSampler {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.next_dimension()
+    }
+
+    fn get_2d(&mut self) -> Vector2<f64> {
+        Vector2::new(self.next_dimension(), self.next_dimension())
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,56 @@
+use nalgebra::Vector2;
+
+use super::{hash, Sampler, SplitMix};
+
+/// Uniform random numbers without any stratification, the baseline other samplers improve on.
+#[derive(Debug, Clone)]
+pub struct IndependentSampler {
+    samples_per_pixel: usize,
+    seed: u64,
+    rng: SplitMix,
+}
+
+impl IndependentSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+            rng: SplitMix::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.rng = SplitMix::new(hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            sample_index as u64,
+            self.seed,
+        ]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    fn get_2d(&mut self) -> Vector2<f64> {
+        Vector2::new(self.rng.next_f64(), self.rng.next_f64())
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
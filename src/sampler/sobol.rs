@@ -0,0 +1,106 @@
+use nalgebra::Vector2;
+
+use super::{fraction, hash, owen_scramble, permutation_element, Sampler};
+
+/// The first dimension of the Sobol sequence, the van der Corput sequence in base 2.
+pub(crate) fn sobol_0(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+/// The second dimension of the Sobol sequence.
+pub(crate) fn sobol_1(mut index: u32) -> u32 {
+    let mut direction = 1 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Owen scrambled Sobol points. The first two Sobol dimensions form a (0, 2)-sequence, which is
+/// stratified in every elementary interval, and each 1D or 2D sample uses them with a separate
+/// scramble and shuffled sample order. This padding keeps the quality of the first dimensions in
+/// all of them. Works best with a power of two samples per pixel.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    samples_per_pixel: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.next_power_of_two(),
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// The shuffled index of the current sample in the next dimension, with the seed for its
+    /// scramble.
+    fn next_index(&mut self) -> (u32, u64) {
+        let count = self.samples_per_pixel;
+        let round = self.sample_index / count;
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            round as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+
+        let index = permutation_element(
+            (self.sample_index % count) as u32,
+            count as u32,
+            seed as u32,
+        );
+        (index, seed)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, seed) = self.next_index();
+        fraction(owen_scramble(sobol_0(index), seed as u32))
+    }
+
+    fn get_2d(&mut self) -> Vector2<f64> {
+        let (index, seed) = self.next_index();
+        Vector2::new(
+            fraction(owen_scramble(sobol_0(index), seed as u32)),
+            fraction(owen_scramble(sobol_1(index), (seed >> 32) as u32)),
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
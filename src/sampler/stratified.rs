@@ -0,0 +1,111 @@
+use nalgebra::Vector2;
+
+use super::{hash, permutation_element, Sampler, SplitMix, ONE_MINUS_EPSILON};
+
+/// Divides every dimension of a pixel into as many strata as there are samples and places one
+/// sample in each, jittered within its stratum. 2D samples use a grid of `x_samples` by
+/// `y_samples` strata. The strata of different dimensions are shuffled independently so they
+/// don't correlate.
+#[derive(Debug, Clone)]
+pub struct StratifiedSampler {
+    x_samples: usize,
+    y_samples: usize,
+    jitter: bool,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+    rng: SplitMix,
+}
+
+impl StratifiedSampler {
+    /// Without `jitter` every sample sits at the center of its stratum.
+    pub fn new(x_samples: usize, y_samples: usize, jitter: bool, seed: u64) -> Self {
+        assert!(
+            x_samples > 0 && y_samples > 0,
+            "Need at least one stratum in each direction"
+        );
+        Self {
+            x_samples,
+            y_samples,
+            jitter,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            rng: SplitMix::new(seed),
+        }
+    }
+
+    /// The stratum of the current sample in the next dimension, and a jitter within it.
+    fn next_stratum(&mut self) -> (usize, Vector2<f64>) {
+        let count = self.samples_per_pixel();
+        // Indices past the strata start another, differently shuffled set.
+        let round = self.sample_index / count;
+        let seed = hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            round as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+
+        let stratum = permutation_element(
+            (self.sample_index % count) as u32,
+            count as u32,
+            seed as u32,
+        );
+        let jitter = if self.jitter {
+            Vector2::new(self.rng.next_f64(), self.rng.next_f64())
+        } else {
+            Vector2::new(0.5, 0.5)
+        };
+        (stratum as usize, jitter)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn samples_per_pixel(&self) -> usize {
+        self.x_samples * self.y_samples
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = SplitMix::new(hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            sample_index as u64,
+            self.seed,
+        ]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (stratum, jitter) = self.next_stratum();
+        ((stratum as f64 + jitter.x) / self.samples_per_pixel() as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> Vector2<f64> {
+        let (stratum, jitter) = self.next_stratum();
+        let x = (stratum % self.x_samples) as f64 + jitter.x;
+        let y = (stratum / self.x_samples) as f64 + jitter.y;
+        Vector2::new(
+            (x / self.x_samples as f64).min(ONE_MINUS_EPSILON),
+            (y / self.y_samples as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
@@ -1,38 +1,98 @@
-use nalgebra::Point3;
-use rand::{thread_rng, Rng};
-use rand_distr::WeightedAliasIndex;
+use nalgebra::{Point3, Vector3};
 
-use crate::{object::ObjectDefinition, shape::IntersectionInfo, Camera, Material, Object, Ray};
+use crate::{
+    bvh::Bvh,
+    function_approximation::PiecewiseConstant,
+    light::{EnvironmentLight, Light},
+    object::ObjectDefinition,
+    shape::IntersectionInfo,
+    BoundingBox, Camera, Object, Ray,
+};
 
 pub struct Scene {
     pub camera: Camera,
     pub objects: Vec<Object>,
-    light_indices: Vec<usize>,
-    light_distribution: WeightedAliasIndex<f64>,
+    lights: Vec<Light>,
+    light_probabilities: Vec<f64>,
+    light_distribution: Option<PiecewiseConstant>,
+    /// Index of the environment light in `lights`.
+    environment: Option<usize>,
+    /// Indices in `lights` of the lights that rays leaving the scene see.
+    infinite_lights: Vec<usize>,
+    bounding_sphere: (Point3<f64>, f64),
+    bvh: Bvh,
 }
 
 impl Scene {
     pub fn new(camera: Camera, objects: Vec<ObjectDefinition>) -> Self {
-        let objects: Vec<Object> = objects.into_iter().map(Object::new).collect();
-        let light_indices: Vec<usize> = objects
+        Self::with_lights(camera, objects, Vec::new())
+    }
+
+    /// A scene with point, directional and environment lights in addition to its emissive
+    /// objects, which become area lights on their own. Lights are sampled in proportion to their
+    /// power. Rays that leave the scene see the first environment light and directional lights
+    /// with a size.
+    pub fn with_lights(camera: Camera, objects: Vec<ObjectDefinition>, lights: Vec<Light>) -> Self {
+        let mut objects: Vec<Object> = objects.into_iter().map(Object::new).collect();
+
+        let object_bounds: Vec<BoundingBox> = objects.iter().map(Object::bounding_box).collect();
+        let bvh = Bvh::build(&object_bounds);
+
+        let bounds = object_bounds
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, other| bounds.union(other));
+        let bounding_sphere = if bounds.is_empty() {
+            (Point3::origin(), 0.)
+        } else {
+            (bounds.centroid(), bounds.size().magnitude() / 2.)
+        };
+
+        let lights: Vec<Light> = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, object)| object.material().is_emissive().then_some(Light::Area(i)))
+            .chain(lights)
+            .collect();
+
+        let powers: Vec<f64> = lights
+            .iter()
+            .map(|light| light.power(&objects, bounding_sphere.1))
+            .collect();
+        let total_power: f64 = powers.iter().sum();
+        let light_probabilities: Vec<f64> =
+            powers.iter().map(|power| power / total_power).collect();
+        let light_distribution = (total_power > 0.).then(|| PiecewiseConstant::build(&powers));
+
+        for (light, probability) in lights.iter().zip(&light_probabilities) {
+            if let Light::Area(index) = light {
+                objects[*index].light_probability = *probability;
+            }
+        }
+
+        let environment = lights
+            .iter()
+            .position(|light| matches!(light, Light::Environment(_)));
+        let infinite_lights = lights
             .iter()
             .enumerate()
-            .filter_map(|(i, object)| {
-                if matches!(object.material(), Material::Emissive { .. }) {
-                    Some(i)
-                } else {
-                    None
-                }
+            .filter(|(i, light)| match light {
+                Light::Environment(_) => Some(*i) == environment,
+                Light::Directional(directional) => !directional.is_delta(),
+                _ => false,
             })
+            .map(|(i, _)| i)
             .collect();
 
-        let light_areas = light_indices.iter().map(|i| objects[*i].area()).collect();
-        let light_distribution = WeightedAliasIndex::new(light_areas).unwrap();
         Self {
             camera,
             objects,
-            light_indices,
+            lights,
+            light_probabilities,
             light_distribution,
+            environment,
+            infinite_lights,
+            bounding_sphere,
+            bvh,
         }
     }
 
@@ -46,25 +106,33 @@ impl Scene {
             direction,
         };
 
-        if let Some((_, intersection)) = self.intersection(&ray) {
-            distance <= intersection.distance + 0.002
-        } else {
-            true
-        }
+        !self.occluded(&ray, distance - 0.002)
+    }
+
+    /// Whether anything blocks the ray before `max_distance`. This stops at the first blocker
+    /// found, so it is cheaper than [`Scene::intersection`] for shadow rays.
+    pub fn occluded(&self, ray: &Ray, max_distance: f64) -> bool {
+        self.bvh.any_hit(ray, max_distance, |index, max_distance| {
+            self.objects[index].blocks(ray, max_distance)
+        })
     }
 
     pub fn intersection(&self, ray: &Ray) -> Option<(&Object, IntersectionInfo)> {
         let mut closest_intersection = None;
-        let mut closest_distance = f64::INFINITY;
-
-        for object in &self.objects {
-            if let Some(intersection) = object.local_intersection(ray) {
-                if intersection.distance >= 0. && intersection.distance < closest_distance {
-                    closest_intersection = Some((object, intersection));
-                    closest_distance = intersection.distance;
-                }
-            }
-        }
+
+        self.bvh
+            .intersect(ray, f64::INFINITY, |index, closest_distance| {
+                let object = &self.objects[index];
+                object
+                    .local_intersection(ray)
+                    .filter(|intersection| {
+                        intersection.distance >= 0. && intersection.distance < closest_distance
+                    })
+                    .map(|intersection| {
+                        closest_intersection = Some((object, intersection));
+                        intersection.distance
+                    })
+            });
 
         closest_intersection.map(|(object, intersection)| {
             (
@@ -74,9 +142,57 @@ impl Scene {
         })
     }
 
-    pub fn random_light(&self) -> &Object {
-        let mut rng = thread_rng();
-        let index = rng.sample(&self.light_distribution);
-        &self.objects[self.light_indices[index]]
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// Picks a light in proportion to its power with a number `u` in [0, 1), returns it with
+    /// the probability of picking it.
+    pub fn sample_light(&self, u: f64) -> Option<(&Light, f64)> {
+        let distribution = self.light_distribution.as_ref()?;
+        let (_, _, index) = distribution.sample_at(u);
+        Some((&self.lights[index], self.light_probabilities[index]))
+    }
+
+    /// The environment light with the probability that [`Scene::sample_light`] picks it.
+    pub fn environment_light(&self) -> Option<(&Light, f64)> {
+        self.environment
+            .map(|index| (&self.lights[index], self.light_probabilities[index]))
+    }
+
+    pub fn environment(&self) -> Option<&EnvironmentLight> {
+        match self.environment_light() {
+            Some((Light::Environment(environment), _)) => Some(environment),
+            _ => None,
+        }
+    }
+
+    /// The lights that rays leaving the scene see, with the probability that
+    /// [`Scene::sample_light`] picks them.
+    pub fn infinite_lights(&self) -> impl Iterator<Item = (&Light, f64)> {
+        self.infinite_lights
+            .iter()
+            .map(|&index| (&self.lights[index], self.light_probabilities[index]))
+    }
+
+    /// Solid angle density of finding `direction` by picking a light with
+    /// [`Scene::sample_light`] and sampling it, summed over the lights that rays leaving the
+    /// scene see.
+    pub fn infinite_light_pdf(&self, direction: &Vector3<f64>) -> f64 {
+        self.infinite_lights()
+            .map(|(light, probability)| probability * light.direction_pdf(direction))
+            .sum()
+    }
+
+    /// Radiance arriving from `direction` for rays that leave the scene.
+    pub fn background(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        self.infinite_lights()
+            .map(|(light, _)| light.radiance(direction))
+            .sum()
+    }
+
+    /// The center and radius of a sphere that contains all objects.
+    pub fn bounding_sphere(&self) -> (Point3<f64>, f64) {
+        self.bounding_sphere
     }
 }
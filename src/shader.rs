@@ -1,13 +1,51 @@
+use std::sync::Arc;
+
 use nalgebra as na;
 
-use na::Vector3;
+use na::{Point3, Vector2, Vector3};
+
+use crate::shape::IntersectionInfo;
+
+mod combinators;
+mod image_texture;
+mod noise;
+
+pub use combinators::{luminance, Add, ColorRamp, Mix, Multiply, Remap, Transform, Triplanar};
+pub use image_texture::{ImageTexture, WrapMode};
+pub use noise::{
+    fbm, turbulence, Cellular, Fbm, Marble, Noise, Perlin, Simplex, Turbulence, Wood, Worley,
+};
+
+/// The surface point a shader is evaluated at.
+#[derive(Debug, Clone, Copy)]
+pub struct ShadingPoint {
+    pub position: Point3<f64>,
+    pub normal: Vector3<f64>,
+    pub uv: Vector2<f64>,
+}
+
+impl From<&IntersectionInfo> for ShadingPoint {
+    fn from(intersection: &IntersectionInfo) -> Self {
+        Self {
+            position: intersection.position,
+            normal: intersection.normal,
+            uv: intersection.uv,
+        }
+    }
+}
 
 pub trait Shader: Send + Sync {
-    fn shade(&self, local_position: &Vector3<f64>) -> Vector3<f64>;
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64>;
+}
+
+impl<S: Shader + ?Sized> Shader for Arc<S> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        (**self).shade(point)
+    }
 }
 
 impl Shader for Vector3<f64> {
-    fn shade(&self, _local_position: &Vector3<f64>) -> Vector3<f64> {
+    fn shade(&self, _point: &ShadingPoint) -> Vector3<f64> {
         *self
     }
 }
@@ -30,8 +68,8 @@ impl Checkerboard {
 }
 
 impl Shader for Checkerboard {
-    fn shade(&self, local_position: &Vector3<f64>) -> Vector3<f64> {
-        let scaled = local_position / self.scale;
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let scaled = point.position.coords / self.scale;
 
         if ((scaled.x + 10000.) as i32 % 2 == 0)
             ^ ((scaled.y + 10000.) as i32 % 2 == 0)
@@ -0,0 +1,239 @@
+use nalgebra as na;
+
+use na::{Similarity3, Vector2, Vector3};
+
+use crate::{Shader, ShadingPoint};
+
+/// Relative luminance of a linear RGB color.
+pub fn luminance(color: &Vector3<f64>) -> f64 {
+    color.dot(&Vector3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Blends from `a` to `b` by the output of `mask`, per color channel, so black selects `a` and
+/// white selects `b`.
+#[derive(Debug, Clone)]
+pub struct Mix<A, B, M> {
+    a: A,
+    b: B,
+    mask: M,
+}
+
+impl<A: Shader, B: Shader, M: Shader> Mix<A, B, M> {
+    pub fn new(a: A, b: B, mask: M) -> Self {
+        Self { a, b, mask }
+    }
+}
+
+impl<A: Shader, B: Shader, M: Shader> Shader for Mix<A, B, M> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let mask = self.mask.shade(point).map(|x| x.clamp(0., 1.));
+        let a = self.a.shade(point);
+        let b = self.b.shade(point);
+        a.component_mul(&mask.map(|x| 1. - x)) + b.component_mul(&mask)
+    }
+}
+
+/// The component-wise product of two shaders, for example to tint a texture.
+#[derive(Debug, Clone)]
+pub struct Multiply<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Shader, B: Shader> Multiply<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Shader, B: Shader> Shader for Multiply<A, B> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        self.a.shade(point).component_mul(&self.b.shade(point))
+    }
+}
+
+/// The sum of two shaders.
+#[derive(Debug, Clone)]
+pub struct Add<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Shader, B: Shader> Add<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Shader, B: Shader> Shader for Add<A, B> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        self.a.shade(point) + self.b.shade(point)
+    }
+}
+
+/// Linearly maps each channel of a shader from the range `[from_min, from_max]` to
+/// `[to_min, to_max]`, clamping values outside of it.
+#[derive(Debug, Clone)]
+pub struct Remap<S> {
+    shader: S,
+    from_min: f64,
+    from_max: f64,
+    to_min: f64,
+    to_max: f64,
+}
+
+impl<S: Shader> Remap<S> {
+    pub fn new(shader: S, from_min: f64, from_max: f64, to_min: f64, to_max: f64) -> Self {
+        Self {
+            shader,
+            from_min,
+            from_max,
+            to_min,
+            to_max,
+        }
+    }
+}
+
+impl<S: Shader> Shader for Remap<S> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        self.shader.shade(point).map(|x| {
+            let t = ((x - self.from_min) / (self.from_max - self.from_min)).clamp(0., 1.);
+            self.to_min + t * (self.to_max - self.to_min)
+        })
+    }
+}
+
+/// Maps the luminance of a shader to a color by interpolating linearly between stops, each a
+/// position and the color at that position. Values beyond the first and last stops take their
+/// colors.
+#[derive(Debug, Clone)]
+pub struct ColorRamp<S> {
+    shader: S,
+    stops: Vec<(f64, Vector3<f64>)>,
+}
+
+impl<S: Shader> ColorRamp<S> {
+    pub fn new(shader: S, mut stops: Vec<(f64, Vector3<f64>)>) -> Self {
+        assert!(!stops.is_empty(), "A color ramp needs at least one stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { shader, stops }
+    }
+}
+
+impl<S: Shader> Shader for ColorRamp<S> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let value = luminance(&self.shader.shade(point));
+
+        let index = self
+            .stops
+            .partition_point(|(position, _)| *position <= value);
+        if index == 0 {
+            return self.stops[0].1;
+        }
+        if index == self.stops.len() {
+            return self.stops[index - 1].1;
+        }
+
+        let (start, color_a) = self.stops[index - 1];
+        let (end, color_b) = self.stops[index];
+        let t = (value - start) / (end - start);
+        color_a * (1. - t) + color_b * t
+    }
+}
+
+/// Looks up a shader at transformed coordinates. The position and normal are mapped by the
+/// inverse of `transform`, so scaling the transform up makes the pattern larger. `uv_scale` and
+/// `uv_offset` do the same for texture coordinates.
+#[derive(Debug, Clone)]
+pub struct Transform<S> {
+    shader: S,
+    inverse_transform: Similarity3<f64>,
+    uv_scale: Vector2<f64>,
+    uv_offset: Vector2<f64>,
+}
+
+impl<S: Shader> Transform<S> {
+    pub fn new(shader: S, transform: Similarity3<f64>) -> Self {
+        Self {
+            shader,
+            inverse_transform: transform.inverse(),
+            uv_scale: Vector2::new(1., 1.),
+            uv_offset: Vector2::zeros(),
+        }
+    }
+
+    /// Only transforms the texture coordinates, as `uv * scale + offset`.
+    pub fn new_uv(shader: S, scale: Vector2<f64>, offset: Vector2<f64>) -> Self {
+        Self {
+            shader,
+            inverse_transform: Similarity3::identity(),
+            uv_scale: scale,
+            uv_offset: offset,
+        }
+    }
+}
+
+impl<S: Shader> Shader for Transform<S> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        self.shader.shade(&ShadingPoint {
+            position: self.inverse_transform.transform_point(&point.position),
+            normal: self
+                .inverse_transform
+                .transform_vector(&point.normal)
+                .normalize(),
+            uv: point.uv.component_mul(&self.uv_scale) + self.uv_offset,
+        })
+    }
+}
+
+/// Projects a shader along the three coordinate axes and blends the projections by how much
+/// the normal faces each axis. This textures surfaces without usable texture coordinates: the
+/// inner shader is looked up with the position on the projection plane, divided by `scale`, as
+/// its texture coordinates. Higher `sharpness` narrows the blend between projections.
+#[derive(Debug, Clone)]
+pub struct Triplanar<S> {
+    shader: S,
+    scale: f64,
+    sharpness: f64,
+}
+
+impl<S: Shader> Triplanar<S> {
+    pub fn new(shader: S, scale: f64, sharpness: f64) -> Self {
+        Self {
+            shader,
+            scale,
+            sharpness,
+        }
+    }
+}
+
+impl<S: Shader> Shader for Triplanar<S> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let weights = point.normal.map(|x| x.abs().powf(self.sharpness));
+        let total = weights.sum();
+        if total == 0. {
+            return Vector3::zeros();
+        }
+
+        let p = point.position.coords / self.scale;
+        let projections = [
+            Vector2::new(p.y, p.z),
+            Vector2::new(p.x, p.z),
+            Vector2::new(p.x, p.y),
+        ];
+
+        projections
+            .into_iter()
+            .zip(weights.iter())
+            .filter(|(_, weight)| **weight > 0.)
+            .map(|(uv, weight)| {
+                self.shader.shade(&ShadingPoint {
+                    position: point.position,
+                    normal: point.normal,
+                    uv,
+                }) * *weight
+            })
+            .sum::<Vector3<f64>>()
+            / total
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use image::{DynamicImage, ImageError};
+use nalgebra as na;
+
+use na::Vector3;
+
+use crate::{Shader, ShadingPoint};
+
+/// How texture coordinates outside of [0, 1] are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Tile the image.
+    Repeat,
+    /// Tile the image, mirroring every other copy so the edges line up.
+    Mirror,
+    /// Extend the edge pixels.
+    Clamp,
+}
+
+impl WrapMode {
+    fn apply(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        match self {
+            WrapMode::Repeat => index.rem_euclid(size) as usize,
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index as usize
+                } else {
+                    (2 * size - 1 - index) as usize
+                }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1) as usize,
+        }
+    }
+}
+
+/// A shader that looks up the texture coordinates of a surface in an image, with bilinear
+/// filtering. Colors are stored in linear space.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3<f64>>,
+    wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+    /// Loads an image from a file in any format supported by the `image` crate, such as PNG or
+    /// EXR.
+    pub fn open<P: AsRef<Path>>(path: P, wrap_mode: WrapMode) -> Result<Self, ImageError> {
+        let image = image::open(path)?;
+        Ok(Self::from_image(&image, wrap_mode))
+    }
+
+    /// Floating point images are assumed to be linear already, all others are converted from
+    /// sRGB.
+    pub fn from_image(image: &DynamicImage, wrap_mode: WrapMode) -> Self {
+        let is_linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+
+        let rgb = image.to_rgb32f();
+        let pixels = rgb
+            .pixels()
+            .map(|pixel| {
+                let color = Vector3::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+                if is_linear {
+                    color
+                } else {
+                    color.map(|x| x.powf(2.2))
+                }
+            })
+            .collect();
+
+        Self {
+            width: rgb.width() as usize,
+            height: rgb.height() as usize,
+            pixels,
+            wrap_mode,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector3<f64> {
+        let x = self.wrap_mode.apply(x, self.width);
+        let y = self.wrap_mode.apply(y, self.height);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Shader for ImageTexture {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        // v points up while image rows go down, texel centers sit at half pixel offsets.
+        let x = point.uv.x * self.width as f64 - 0.5;
+        let y = (1. - point.uv.y) * self.height as f64 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let dx = x - x0;
+        let dy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        self.texel(x0, y0) * (1. - dx) * (1. - dy)
+            + self.texel(x0 + 1, y0) * dx * (1. - dy)
+            + self.texel(x0, y0 + 1) * (1. - dx) * dy
+            + self.texel(x0 + 1, y0 + 1) * dx * dy
+    }
+}
@@ -0,0 +1,469 @@
+use std::f64::consts::TAU;
+
+use nalgebra as na;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use na::{Point3, Vector3};
+
+use crate::{Shader, ShadingPoint};
+
+/// A scalar noise field over 3D space.
+pub trait Noise: Send + Sync {
+    /// The value of the field at `position`, roughly in [-1, 1].
+    fn noise(&self, position: &Point3<f64>) -> f64;
+}
+
+/// Doubled random permutation of 0..256, so that lookups of `index + offset` need no wrapping.
+fn permutation_table(seed: u64) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut permutation: Vec<u8> = (0..=255).collect();
+    permutation.shuffle(&mut rng);
+    permutation.extend_from_within(..);
+    permutation
+}
+
+/// Dot product of the offset with one of the 12 cube edge directions picked by `hash`.
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Ken Perlin's improved gradient noise.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    permutation: Vec<u8>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: permutation_table(seed),
+        }
+    }
+
+    fn hash(&self, x: usize, y: usize, z: usize) -> u8 {
+        let p = &self.permutation;
+        p[p[p[x] as usize + y] as usize + z]
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Noise for Perlin {
+    fn noise(&self, position: &Point3<f64>) -> f64 {
+        let cell = position.map(f64::floor);
+        let (x, y, z) = (
+            position.x - cell.x,
+            position.y - cell.y,
+            position.z - cell.z,
+        );
+        let (i, j, k) = (
+            cell.x.rem_euclid(256.) as usize,
+            cell.y.rem_euclid(256.) as usize,
+            cell.z.rem_euclid(256.) as usize,
+        );
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(self.hash(i, j, k), x, y, z),
+                    gradient(self.hash(i + 1, j, k), x - 1., y, z),
+                ),
+                lerp(
+                    u,
+                    gradient(self.hash(i, j + 1, k), x, y - 1., z),
+                    gradient(self.hash(i + 1, j + 1, k), x - 1., y - 1., z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(self.hash(i, j, k + 1), x, y, z - 1.),
+                    gradient(self.hash(i + 1, j, k + 1), x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    gradient(self.hash(i, j + 1, k + 1), x, y - 1., z - 1.),
+                    gradient(self.hash(i + 1, j + 1, k + 1), x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+}
+
+/// Simplex noise, which interpolates over a tetrahedral grid instead of cubes. It is cheaper
+/// than [`Perlin`] noise and has fewer axis-aligned artifacts.
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    permutation: Vec<u8>,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: permutation_table(seed),
+        }
+    }
+
+    fn hash(&self, x: usize, y: usize, z: usize) -> u8 {
+        let p = &self.permutation;
+        p[p[p[z] as usize + y] as usize + x]
+    }
+}
+
+impl Default for Simplex {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Noise for Simplex {
+    fn noise(&self, position: &Point3<f64>) -> f64 {
+        const F3: f64 = 1. / 3.;
+        const G3: f64 = 1. / 6.;
+
+        // Skew to find the containing simplex cell, then unskew back to get the offset.
+        let s = (position.x + position.y + position.z) * F3;
+        let cell = position.map(|x| (x + s).floor());
+        let t = (cell.x + cell.y + cell.z) * G3;
+        let d0 = position - cell.map(|x| x - t);
+
+        // Corners are visited in order of the largest offset coordinates.
+        let (c1, c2) = if d0.x >= d0.y {
+            if d0.y >= d0.z {
+                ([1, 0, 0], [1, 1, 0])
+            } else if d0.x >= d0.z {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if d0.y < d0.z {
+            ([0, 0, 1], [0, 1, 1])
+        } else if d0.x < d0.z {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+
+        let (i, j, k) = (
+            cell.x.rem_euclid(256.) as usize,
+            cell.y.rem_euclid(256.) as usize,
+            cell.z.rem_euclid(256.) as usize,
+        );
+
+        let corners = [[0, 0, 0], c1, c2, [1, 1, 1]];
+        let sum: f64 = corners
+            .iter()
+            .enumerate()
+            .map(|(n, c)| {
+                let offset = Vector3::new(c[0] as f64, c[1] as f64, c[2] as f64);
+                let d = d0 - offset + Vector3::repeat(n as f64 * G3);
+                let falloff = 0.6 - d.magnitude_squared();
+                if falloff < 0. {
+                    return 0.;
+                }
+                let hash = self.hash(i + c[0], j + c[1], k + c[2]);
+                falloff.powi(4) * gradient(hash % 12, d.x, d.y, d.z)
+            })
+            .sum();
+
+        32. * sum
+    }
+}
+
+/// Cellular noise after Worley, "A Cellular Texture Basis Function", based on the distance to
+/// the closest of a set of feature points jittered inside a unit grid.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Worley {
+    seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// The feature point of the grid cell at `cell`.
+    fn feature_point(&self, cell: &Vector3<i64>) -> Vector3<f64> {
+        let mut state = self.seed;
+        for coordinate in cell.iter() {
+            state = split_mix(state ^ *coordinate as u64);
+        }
+
+        let jitter = Vector3::from_fn(|_, _| {
+            state = split_mix(state);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        });
+
+        cell.map(|x| x as f64) + jitter
+    }
+
+    /// Distance from `position` to the closest feature point.
+    pub fn distance(&self, position: &Point3<f64>) -> f64 {
+        let cell = position.coords.map(|x| x.floor() as i64);
+
+        let mut closest = f64::INFINITY;
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let neighbor = cell + Vector3::new(x, y, z);
+                    let distance = (self.feature_point(&neighbor) - position.coords).magnitude();
+                    closest = closest.min(distance);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+impl Noise for Worley {
+    /// The distance to the closest feature point, remapped from [0, 1] to [-1, 1].
+    fn noise(&self, position: &Point3<f64>) -> f64 {
+        (2. * self.distance(position) - 1.).clamp(-1., 1.)
+    }
+}
+
+fn split_mix(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Fractal Brownian motion: octaves of `noise` at doubling frequencies and halving amplitudes,
+/// normalized to stay within the range of a single octave.
+pub fn fbm<N: Noise + ?Sized>(noise: &N, position: &Point3<f64>, octaves: u32) -> f64 {
+    octave_sum(noise, position, octaves, |x| x)
+}
+
+/// Like [`fbm`], but summing the absolute value of each octave, which gives creases where the
+/// noise crosses zero. The result is in [0, 1].
+pub fn turbulence<N: Noise + ?Sized>(noise: &N, position: &Point3<f64>, octaves: u32) -> f64 {
+    octave_sum(noise, position, octaves, f64::abs)
+}
+
+fn octave_sum<N: Noise + ?Sized>(
+    noise: &N,
+    position: &Point3<f64>,
+    octaves: u32,
+    f: impl Fn(f64) -> f64,
+) -> f64 {
+    let mut sum = 0.;
+    let mut total_amplitude = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+
+    for _ in 0..octaves.max(1) {
+        sum += amplitude * f(noise.noise(&(position * frequency)));
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+
+    sum / total_amplitude
+}
+
+fn mix(color_a: &Vector3<f64>, color_b: &Vector3<f64>, t: f64) -> Vector3<f64> {
+    color_a * (1. - t) + color_b * t
+}
+
+/// Blends between two colors by fractal noise, where `scale` is the size of the largest
+/// features.
+#[derive(Debug, Clone)]
+pub struct Fbm<N = Perlin> {
+    noise: N,
+    color_a: Vector3<f64>,
+    color_b: Vector3<f64>,
+    scale: f64,
+    octaves: u32,
+}
+
+impl<N: Noise> Fbm<N> {
+    pub fn new(
+        noise: N,
+        color_a: Vector3<f64>,
+        color_b: Vector3<f64>,
+        scale: f64,
+        octaves: u32,
+    ) -> Self {
+        Self {
+            noise,
+            color_a,
+            color_b,
+            scale,
+            octaves,
+        }
+    }
+}
+
+impl<N: Noise> Shader for Fbm<N> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let value = fbm(&self.noise, &(point.position / self.scale), self.octaves);
+        mix(
+            &self.color_a,
+            &self.color_b,
+            (value * 0.5 + 0.5).clamp(0., 1.),
+        )
+    }
+}
+
+/// Blends between two colors by turbulence, giving billowy, cloud-like patterns.
+#[derive(Debug, Clone)]
+pub struct Turbulence<N = Perlin> {
+    noise: N,
+    color_a: Vector3<f64>,
+    color_b: Vector3<f64>,
+    scale: f64,
+    octaves: u32,
+}
+
+impl<N: Noise> Turbulence<N> {
+    pub fn new(
+        noise: N,
+        color_a: Vector3<f64>,
+        color_b: Vector3<f64>,
+        scale: f64,
+        octaves: u32,
+    ) -> Self {
+        Self {
+            noise,
+            color_a,
+            color_b,
+            scale,
+            octaves,
+        }
+    }
+}
+
+impl<N: Noise> Shader for Turbulence<N> {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let value = turbulence(&self.noise, &(point.position / self.scale), self.octaves);
+        mix(&self.color_a, &self.color_b, value.clamp(0., 1.))
+    }
+}
+
+/// Cells around randomly placed points, colored `color_a` at their centers and `color_b`
+/// towards their borders.
+#[derive(Debug, Clone, Copy)]
+pub struct Cellular {
+    worley: Worley,
+    color_a: Vector3<f64>,
+    color_b: Vector3<f64>,
+    scale: f64,
+}
+
+impl Cellular {
+    pub fn new(seed: u64, color_a: Vector3<f64>, color_b: Vector3<f64>, scale: f64) -> Self {
+        Self {
+            worley: Worley::new(seed),
+            color_a,
+            color_b,
+            scale,
+        }
+    }
+}
+
+impl Shader for Cellular {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let distance = self.worley.distance(&(point.position / self.scale));
+        mix(&self.color_a, &self.color_b, distance.clamp(0., 1.))
+    }
+}
+
+/// Veins of `color_b` in `color_a`, made from stripes along the x axis that are distorted by
+/// turbulence. `scale` is the distance between veins.
+#[derive(Debug, Clone)]
+pub struct Marble {
+    noise: Perlin,
+    color_a: Vector3<f64>,
+    color_b: Vector3<f64>,
+    scale: f64,
+    distortion: f64,
+}
+
+impl Marble {
+    pub fn new(color_a: Vector3<f64>, color_b: Vector3<f64>, scale: f64, distortion: f64) -> Self {
+        Self {
+            noise: Perlin::default(),
+            color_a,
+            color_b,
+            scale,
+            distortion,
+        }
+    }
+}
+
+impl Shader for Marble {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let position = point.position / self.scale;
+        let phase = position.x + self.distortion * turbulence(&self.noise, &position, 6);
+        let vein = 1. - (phase * TAU / 2.).sin().abs();
+        mix(&self.color_a, &self.color_b, vein.powi(4))
+    }
+}
+
+/// Concentric growth rings around the local z axis, alternating from `color_a` to `color_b`
+/// and slightly warped by noise. `scale` is the distance between rings.
+#[derive(Debug, Clone)]
+pub struct Wood {
+    noise: Perlin,
+    color_a: Vector3<f64>,
+    color_b: Vector3<f64>,
+    scale: f64,
+    distortion: f64,
+}
+
+impl Wood {
+    pub fn new(color_a: Vector3<f64>, color_b: Vector3<f64>, scale: f64, distortion: f64) -> Self {
+        Self {
+            noise: Perlin::default(),
+            color_a,
+            color_b,
+            scale,
+            distortion,
+        }
+    }
+}
+
+impl Shader for Wood {
+    fn shade(&self, point: &ShadingPoint) -> Vector3<f64> {
+        let position = point.position / self.scale;
+        let radius = position.x.hypot(position.y);
+        // Stretch the noise along the grain so rings wobble slowly along the trunk.
+        let warp = self.noise.noise(&Point3::new(
+            position.x * 2.,
+            position.y * 2.,
+            position.z * 0.25,
+        ));
+        let ring = (radius + self.distortion * warp).rem_euclid(1.);
+        // Early wood grows gradually, late wood ends in a sharp boundary.
+        mix(&self.color_a, &self.color_b, ring.powi(3))
+    }
+}
@@ -1,37 +1,39 @@
 use nalgebra as na;
 
-use na::{Point3, Similarity3, Vector3};
+use na::{Point3, Similarity3, Vector2, Vector3};
 
-use crate::Ray;
+use crate::{BoundingBox, Ray, ShadingPoint};
 
 mod cuboid;
 mod cylinder;
 mod empty;
 mod plane;
 mod sphere;
+mod triangle;
+mod triangle_mesh;
 
 pub use cuboid::Cuboid;
 pub use cylinder::Cylinder;
 pub use empty::Empty;
 pub use plane::Plane;
 pub use sphere::Sphere;
+pub use triangle::Triangle;
+pub use triangle_mesh::TriangleMesh;
 
 #[derive(Debug, Clone, Copy)]
 pub struct IntersectionInfo {
     pub distance: f64,
     pub position: Point3<f64>,
     pub normal: Vector3<f64>,
+    /// Texture coordinates of the hit, usually in [0, 1].
+    pub uv: Vector2<f64>,
 }
 
 impl IntersectionInfo {
     pub fn transform_similarity(&self, matrix: &Similarity3<f64>) -> Self {
         let new_position = matrix.transform_point(&self.position);
-        let new_normal = matrix
-            .inverse()
-            .to_homogeneous()
-            .transpose()
-            .transform_vector(&self.normal)
-            .normalize();
+        // Similarities keep angles, so normals only need to be rotated.
+        let new_normal = matrix.isometry.rotation * self.normal;
         IntersectionInfo {
             position: new_position,
             normal: new_normal,
@@ -45,24 +47,45 @@ pub trait Shape: Send + Sync {
 
     fn sample_normal(&self, position: Point3<f64>) -> Vector3<f64>;
 
-    fn sample_random_point(&self) -> Point3<f64>;
+    /// Texture coordinates of a point on the surface.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64>;
+
+    /// Maps `u`, uniform in [0, 1)², to a point distributed uniformly over the surface.
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64>;
+
+    /// Like [`Shape::sample_random_point`], but also returns the normal and texture coordinates
+    /// of the point. Shapes that can't look these up cheaply by position override it.
+    fn sample_surface_point(&self, u: Vector2<f64>) -> ShadingPoint {
+        let position = self.sample_random_point(u);
+        ShadingPoint {
+            position,
+            normal: self.sample_normal(position),
+            uv: self.uv(position),
+        }
+    }
 
     fn area(&self) -> f64;
 
+    fn bounding_box(&self) -> BoundingBox;
+
     fn intersection(&self, ray: &Ray) -> Option<IntersectionInfo> {
         self.intersection_distance(ray).map(|distance| {
             let position = ray.origin + distance * ray.direction;
             let normal = self.sample_normal(position);
+            let uv = self.uv(position);
             IntersectionInfo {
                 distance,
                 position,
                 normal,
+                uv,
             }
         })
     }
 
-    fn blocks(&self, ray: &Ray) -> bool {
-        self.intersection_distance(ray).is_some()
+    /// Whether the ray hits the shape before reaching `max_distance`.
+    fn blocks(&self, ray: &Ray, max_distance: f64) -> bool {
+        self.intersection_distance(ray)
+            .is_some_and(|distance| distance < max_distance)
     }
 }
 
@@ -78,11 +101,31 @@ impl<S: Shape> Shape for Inverted<S> {
         self.0.area()
     }
 
-    fn sample_random_point(&self) -> Point3<f64> {
-        self.0.sample_random_point()
+    fn bounding_box(&self) -> BoundingBox {
+        self.0.bounding_box()
+    }
+
+    fn blocks(&self, ray: &Ray, max_distance: f64) -> bool {
+        self.0.blocks(ray, max_distance)
+    }
+
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        self.0.sample_random_point(u)
+    }
+
+    fn sample_surface_point(&self, u: Vector2<f64>) -> ShadingPoint {
+        let point = self.0.sample_surface_point(u);
+        ShadingPoint {
+            normal: -point.normal,
+            ..point
+        }
     }
 
     fn sample_normal(&self, position: Point3<f64>) -> Vector3<f64> {
         -self.0.sample_normal(position)
     }
+
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        self.0.uv(position)
+    }
 }
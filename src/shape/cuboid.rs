@@ -1,10 +1,8 @@
-use crate::{Ray, Shape};
+use crate::{function_approximation::PiecewiseConstant, BoundingBox, Ray, Shape};
 
 use nalgebra as na;
 
-use na::{Point3, Vector3};
-use rand::{thread_rng, Rng};
-use rand_distr::WeightedAliasIndex;
+use na::{Point3, Vector2, Vector3};
 
 pub struct Cuboid {
     pub width: f64,
@@ -25,26 +23,20 @@ impl Cuboid {
 impl Shape for Cuboid {
     fn intersection_distance(&self, ray: &Ray) -> Option<f64> {
         let mut result = f64::INFINITY;
-
-        let ts = [
-            (-ray.origin.x - self.width / 2.) / ray.direction.x,
-            (-ray.origin.x + self.width / 2.) / ray.direction.x,
-            (-ray.origin.y - self.height / 2.) / ray.direction.y,
-            (-ray.origin.y + self.height / 2.) / ray.direction.y,
-            (-ray.origin.z - self.depth / 2.) / ray.direction.z,
-            (-ray.origin.z + self.depth / 2.) / ray.direction.z,
-        ];
-        for t in ts {
-            if t >= 0. && t < result {
-                let position = ray.sample(t);
-                if -self.width / 2. <= position.x
-                    && position.x <= self.width / 2.
-                    && -self.height / 2. <= position.y
-                    && position.y <= self.height / 2.
-                    && -self.depth / 2. <= position.z
-                    && position.z <= self.depth / 2.
-                {
-                    result = t;
+        let half_size = Vector3::new(self.width, self.height, self.depth) / 2.;
+
+        for axis in 0..3 {
+            for side in [-1., 1.] {
+                let t = (side * half_size[axis] - ray.origin[axis]) / ray.direction[axis];
+                if t >= 0. && t < result {
+                    // Only the other axes are checked, the hit is on this face up to rounding.
+                    let position = ray.sample(t);
+                    if (0..3)
+                        .filter(|other| *other != axis)
+                        .all(|other| position[other].abs() <= half_size[other])
+                    {
+                        result = t;
+                    }
                 }
             }
         }
@@ -52,7 +44,6 @@ impl Shape for Cuboid {
         if result.is_finite() {
             Some(result)
         } else {
-            //println!("{ts:?} {ray:?}");
             None
         }
     }
@@ -77,22 +68,38 @@ impl Shape for Cuboid {
         normal
     }
 
-    fn sample_random_point(&self) -> Point3<f64> {
-        let mut rng = thread_rng();
+    /// Every face is mapped to the full [0, 1] range, oriented upright when seen from outside
+    /// with y or, for the top and bottom faces, -z and z as up.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        let normal = self.sample_normal(position);
+        let size = Vector3::new(self.width, self.height, self.depth);
+        let unit_position = position.coords.component_div(&size);
+
+        let (u, v) = if normal.x != 0. {
+            (-normal.x * unit_position.z, unit_position.y)
+        } else if normal.y != 0. {
+            (unit_position.x, -normal.y * unit_position.z)
+        } else {
+            (normal.z * unit_position.x, unit_position.y)
+        };
 
-        let distribution = WeightedAliasIndex::new(vec![
+        Vector2::new(u + 0.5, v + 0.5)
+    }
+
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        let distribution = PiecewiseConstant::build(&[
             self.width * self.height,
             self.height * self.depth,
             self.width * self.depth,
             self.width * self.height,
             self.height * self.depth,
             self.width * self.depth,
-        ])
-        .unwrap();
+        ]);
 
-        let direction: u8 = rng.sample(distribution) as u8;
-        let x = rng.gen_range(-0.5..=0.5);
-        let y = rng.gen_range(-0.5..=0.5);
+        // Picking the face leaves a uniform number within its bin for one of the coordinates.
+        let (selection, _, direction) = distribution.sample_at(u.x);
+        let x = selection * 6. - direction as f64 - 0.5;
+        let y = u.y - 0.5;
         let z = if direction < 3 { -0.5 } else { 0.5 };
 
         let unit_position = match direction % 3 {
@@ -110,4 +117,9 @@ impl Shape for Cuboid {
     fn area(&self) -> f64 {
         2. * (self.width * (self.height + self.depth) + self.height * self.depth)
     }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vector3::new(self.width, self.height, self.depth) / 2.;
+        BoundingBox::new((-extent).into(), extent.into())
+    }
 }
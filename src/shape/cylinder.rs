@@ -1,9 +1,8 @@
 use std::f64::consts::TAU;
 
-use nalgebra::{Point3, Vector3};
-use rand::{thread_rng, Rng};
+use nalgebra::{Point3, Vector2, Vector3};
 
-use crate::Shape;
+use crate::{BoundingBox, Shape};
 
 pub struct Cylinder {
     pub radius: f64,
@@ -63,26 +62,31 @@ impl Shape for Cylinder {
     }
 
     fn sample_normal(&self, position: nalgebra::Point3<f64>) -> nalgebra::Vector3<f64> {
-        let mut rng = thread_rng();
         let xy = position.xy().coords.normalize();
+        Vector3::new(xy.x, xy.y, 0.)
+    }
 
-        if rng.gen_bool(0.5) {
-            Vector3::new(xy.x, xy.y, 0.)
-        } else {
-            Vector3::new(-xy.x, -xy.y, 0.)
-        }
+    /// The angle around the z axis and the height along it.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        let u = (position.y.atan2(position.x) / TAU).rem_euclid(1.);
+        let v = position.z / self.height + 0.5;
+        Vector2::new(u, v)
     }
 
-    fn sample_random_point(&self) -> nalgebra::Point3<f64> {
-        let mut rng = thread_rng();
-        let angle = rng.gen_range(0. ..TAU);
+    fn sample_random_point(&self, u: Vector2<f64>) -> nalgebra::Point3<f64> {
+        let angle = u.x * TAU;
         let x = angle.cos() * self.radius;
-        let z = angle.sin() * self.radius;
-        let y = rng.gen_range(-self.height / 2.0..self.height / 2.);
+        let y = angle.sin() * self.radius;
+        let z = (u.y - 0.5) * self.height;
         Point3::new(x, y, z)
     }
 
     fn area(&self) -> f64 {
-        2. * TAU * self.height
+        TAU * self.radius * self.height
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vector3::new(self.radius, self.radius, self.height / 2.);
+        BoundingBox::new((-extent).into(), extent.into())
     }
 }
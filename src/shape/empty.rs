@@ -1,6 +1,6 @@
-use nalgebra::{Point3, Vector3};
+use nalgebra::{Point3, Vector2, Vector3};
 
-use crate::Shape;
+use crate::{BoundingBox, Shape};
 
 pub struct Empty;
 
@@ -13,11 +13,19 @@ impl Shape for Empty {
         Vector3::zeros()
     }
 
-    fn sample_random_point(&self) -> nalgebra::Point3<f64> {
+    fn uv(&self, _position: Point3<f64>) -> Vector2<f64> {
+        Vector2::zeros()
+    }
+
+    fn sample_random_point(&self, _u: Vector2<f64>) -> nalgebra::Point3<f64> {
         Point3::new(0., 0., 0.)
     }
 
     fn area(&self) -> f64 {
         0.
     }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::empty()
+    }
 }
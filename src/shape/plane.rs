@@ -1,9 +1,8 @@
 use nalgebra as na;
 
-use na::{Point3, Vector3};
-use rand::{thread_rng, Rng};
+use na::{Point3, Vector2, Vector3};
 
-use crate::{Ray, Shape};
+use crate::{BoundingBox, Ray, Shape};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Plane {
@@ -42,25 +41,32 @@ impl Shape for Plane {
         }
     }
 
-    fn sample_random_point(&self) -> Point3<f64> {
-        let mut rng = thread_rng();
-
-        let x = rng.gen_range((-self.width / 2.)..self.width / 2.);
-        let y = rng.gen_range((-self.height / 2.)..self.height / 2.);
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        let x = (u.x - 0.5) * self.width;
+        let y = (u.y - 0.5) * self.height;
 
         Point3::new(x, y, 0.)
     }
 
     fn area(&self) -> f64 {
-        2. * self.width * self.height
+        self.width * self.height
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3::new(-self.width / 2., -self.height / 2., 0.),
+            Point3::new(self.width / 2., self.height / 2., 0.),
+        )
     }
 
     fn sample_normal(&self, _position: Point3<f64>) -> Vector3<f64> {
-        let mut rng = thread_rng();
-        if rng.gen_bool(0.5) {
-            Vector3::new(0., 0., 1.)
-        } else {
-            Vector3::new(0., 0., -1.)
-        }
+        Vector3::new(0., 0., 1.)
+    }
+
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        Vector2::new(
+            position.x / self.width + 0.5,
+            position.y / self.height + 0.5,
+        )
     }
 }
@@ -1,12 +1,10 @@
-use std::f64::consts::PI;
+use std::f64::consts::{PI, TAU};
 
 use nalgebra as na;
 
-use na::{Point3, Vector3};
-use rand::thread_rng;
-use rand_distr::StandardNormal;
+use na::{Point3, Vector2, Vector3};
 
-use crate::{Ray, Shape};
+use crate::{BoundingBox, Ray, Shape};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Sphere {
@@ -50,15 +48,28 @@ impl Shape for Sphere {
         4. * PI * self.radius * self.radius
     }
 
-    fn sample_random_point(&self) -> Point3<f64> {
-        let origin: Point3<f64> = Vector3::from_distribution(&StandardNormal, &mut thread_rng())
-            .normalize()
-            .into();
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vector3::new(self.radius, self.radius, self.radius);
+        BoundingBox::new((-extent).into(), extent.into())
+    }
+
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        let z = 1. - 2. * u.x;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = u.y * TAU;
 
-        origin
+        (Vector3::new(r * phi.cos(), r * phi.sin(), z) * self.radius).into()
     }
 
     fn sample_normal(&self, position: Point3<f64>) -> Vector3<f64> {
         position.coords.normalize()
     }
+
+    /// Longitude and latitude around the z axis.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        let direction = position.coords.normalize();
+        let u = (direction.y.atan2(direction.x) / TAU).rem_euclid(1.);
+        let v = 1. - direction.z.clamp(-1., 1.).acos() / PI;
+        Vector2::new(u, v)
+    }
 }
@@ -0,0 +1,162 @@
+use nalgebra as na;
+
+use na::{Point3, Vector2, Vector3};
+
+use crate::{BoundingBox, Ray, Shape};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Triangle {
+    pub a: Point3<f64>,
+    pub b: Point3<f64>,
+    pub c: Point3<f64>,
+}
+
+impl Triangle {
+    pub fn new(a: Point3<f64>, b: Point3<f64>, c: Point3<f64>) -> Self {
+        Self { a, b, c }
+    }
+
+    pub fn face_normal(&self) -> Vector3<f64> {
+        (self.b - self.a).cross(&(self.c - self.a)).normalize()
+    }
+
+    /// Returns the distance along the ray and the barycentric coordinates of `b` and `c`.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        // Möller–Trumbore
+        let edge_1 = self.b - self.a;
+        let edge_2 = self.c - self.a;
+
+        let p = ray.direction.cross(&edge_2);
+        let determinant = edge_1.dot(&p);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inverse_determinant = 1. / determinant;
+
+        let s = ray.origin - self.a;
+        let u = s.dot(&p) * inverse_determinant;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(&edge_1);
+        let v = ray.direction.dot(&q) * inverse_determinant;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        let t = edge_2.dot(&q) * inverse_determinant;
+        if t >= 0. {
+            Some((t, u, v))
+        } else {
+            None
+        }
+    }
+
+    /// Barycentric coordinates of `b` and `c` for a point projected onto the triangle's plane.
+    pub fn barycentric(&self, position: &Point3<f64>) -> (f64, f64) {
+        let edge_1 = self.b - self.a;
+        let edge_2 = self.c - self.a;
+        let offset = position - self.a;
+
+        let d11 = edge_1.dot(&edge_1);
+        let d12 = edge_1.dot(&edge_2);
+        let d22 = edge_2.dot(&edge_2);
+        let d1 = offset.dot(&edge_1);
+        let d2 = offset.dot(&edge_2);
+
+        let denominator = d11 * d22 - d12 * d12;
+        let u = (d22 * d1 - d12 * d2) / denominator;
+        let v = (d11 * d2 - d12 * d1) / denominator;
+        (u, v)
+    }
+
+    /// Barycentric coordinates of `b` and `c` for the point of the triangle closest to
+    /// `position`, which lies on an edge or corner if the projection falls outside.
+    pub fn closest_barycentric(&self, position: &Point3<f64>) -> (f64, f64) {
+        // Finds the Voronoi region of the point, as in Ericson's Real-Time Collision Detection.
+        let ab = self.b - self.a;
+        let ac = self.c - self.a;
+
+        let ap = position - self.a;
+        let d1 = ab.dot(&ap);
+        let d2 = ac.dot(&ap);
+        if d1 <= 0. && d2 <= 0. {
+            return (0., 0.);
+        }
+
+        let bp = position - self.b;
+        let d3 = ab.dot(&bp);
+        let d4 = ac.dot(&bp);
+        if d3 >= 0. && d4 <= d3 {
+            return (1., 0.);
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+            return (d1 / (d1 - d3), 0.);
+        }
+
+        let cp = position - self.c;
+        let d5 = ab.dot(&cp);
+        let d6 = ac.dot(&cp);
+        if d6 >= 0. && d5 <= d6 {
+            return (0., 1.);
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0. && d2 >= 0. && d6 <= 0. {
+            return (0., d2 / (d2 - d6));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return (1. - w, w);
+        }
+
+        let denominator = 1. / (va + vb + vc);
+        (vb * denominator, vc * denominator)
+    }
+
+    /// Maps `u`, uniform in [0, 1)², to barycentric coordinates of a uniformly distributed point.
+    pub fn sample_barycentric(u: Vector2<f64>) -> (f64, f64) {
+        let r1 = u.x.sqrt();
+        let r2 = u.y;
+
+        (r1 * (1. - r2), r1 * r2)
+    }
+
+    pub fn point_at(&self, u: f64, v: f64) -> Point3<f64> {
+        self.a + (self.b - self.a) * u + (self.c - self.a) * v
+    }
+}
+
+impl Shape for Triangle {
+    fn intersection_distance(&self, ray: &Ray) -> Option<f64> {
+        self.intersect(ray).map(|(t, _, _)| t)
+    }
+
+    fn sample_normal(&self, _position: Point3<f64>) -> Vector3<f64> {
+        self.face_normal()
+    }
+
+    /// The barycentric coordinates of `b` and `c`.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        let (u, v) = self.barycentric(&position);
+        Vector2::new(u, v)
+    }
+
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        let (u, v) = Self::sample_barycentric(u);
+        self.point_at(u, v)
+    }
+
+    fn area(&self) -> f64 {
+        (self.b - self.a).cross(&(self.c - self.a)).magnitude() / 2.
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points([&self.a, &self.b, &self.c])
+    }
+}
@@ -0,0 +1,227 @@
+use nalgebra as na;
+
+use na::{Point3, Vector2, Vector3};
+
+use crate::{
+    bvh::Bvh, function_approximation::PiecewiseConstant, BoundingBox, Ray, ShadingPoint, Shape,
+};
+
+use super::{IntersectionInfo, Triangle};
+
+pub struct TriangleMesh {
+    vertices: Vec<Point3<f64>>,
+    normals: Vec<Vector3<f64>>,
+    indices: Vec<[usize; 3]>,
+    area: f64,
+    area_distribution: PiecewiseConstant,
+    bounding_box: BoundingBox,
+    bvh: Bvh,
+}
+
+impl TriangleMesh {
+    /// Creates a mesh with flat shading, every triangle uses its own face normal.
+    pub fn new(vertices: Vec<Point3<f64>>, indices: Vec<[usize; 3]>) -> Self {
+        let mut flat_vertices = Vec::with_capacity(indices.len() * 3);
+        let mut normals = Vec::with_capacity(indices.len() * 3);
+        let mut flat_indices = Vec::with_capacity(indices.len());
+
+        for [a, b, c] in indices {
+            let triangle = Triangle::new(vertices[a], vertices[b], vertices[c]);
+            let normal = triangle.face_normal();
+
+            let offset = flat_vertices.len();
+            flat_vertices.extend([triangle.a, triangle.b, triangle.c]);
+            normals.extend([normal; 3]);
+            flat_indices.push([offset, offset + 1, offset + 2]);
+        }
+
+        Self::with_normals(flat_vertices, normals, flat_indices)
+    }
+
+    /// Creates a mesh with smooth shading, vertex normals are the area weighted average of the
+    /// normals of the faces that share the vertex.
+    pub fn smooth(vertices: Vec<Point3<f64>>, indices: Vec<[usize; 3]>) -> Self {
+        let mut normals = vec![Vector3::zeros(); vertices.len()];
+
+        for &[a, b, c] in &indices {
+            let weighted_normal = (vertices[b] - vertices[a]).cross(&(vertices[c] - vertices[a]));
+            normals[a] += weighted_normal;
+            normals[b] += weighted_normal;
+            normals[c] += weighted_normal;
+        }
+
+        for normal in &mut normals {
+            normal.normalize_mut();
+        }
+
+        Self::with_normals(vertices, normals, indices)
+    }
+
+    /// Creates a mesh with a normal per vertex, `normals` is indexed the same way as `vertices`.
+    pub fn with_normals(
+        vertices: Vec<Point3<f64>>,
+        normals: Vec<Vector3<f64>>,
+        indices: Vec<[usize; 3]>,
+    ) -> Self {
+        assert!(!indices.is_empty(), "A mesh needs at least one triangle.");
+        assert_eq!(
+            vertices.len(),
+            normals.len(),
+            "Every vertex needs exactly one normal."
+        );
+
+        let areas: Vec<f64> = indices
+            .iter()
+            .map(|&[a, b, c]| Triangle::new(vertices[a], vertices[b], vertices[c]).area())
+            .collect();
+        let area = areas.iter().sum();
+        let area_distribution = PiecewiseConstant::build(&areas);
+
+        let triangle_bounds: Vec<BoundingBox> = indices
+            .iter()
+            .map(|&[a, b, c]| BoundingBox::from_points([&vertices[a], &vertices[b], &vertices[c]]))
+            .collect();
+        let bounding_box = triangle_bounds
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, triangle| {
+                bounds.union(triangle)
+            });
+        let bvh = Bvh::build(&triangle_bounds);
+
+        Self {
+            vertices,
+            normals,
+            indices,
+            area,
+            area_distribution,
+            bounding_box,
+            bvh,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Point3<f64>] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[[usize; 3]] {
+        &self.indices
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn triangle(&self, index: usize) -> Triangle {
+        let [a, b, c] = self.indices[index];
+        Triangle::new(self.vertices[a], self.vertices[b], self.vertices[c])
+    }
+
+    fn interpolated_normal(&self, index: usize, u: f64, v: f64) -> Vector3<f64> {
+        let [a, b, c] = self.indices[index];
+        let normal = self.normals[a] * (1. - u - v) + self.normals[b] * u + self.normals[c] * v;
+
+        if normal.magnitude_squared() > 0. {
+            normal.normalize()
+        } else {
+            self.triangle(index).face_normal()
+        }
+    }
+
+    /// The triangle closest to `position` along with the barycentric coordinates of the closest
+    /// point on it. Only needed for lookups by position, hits and sampled points already know
+    /// their triangle.
+    fn closest_point(&self, position: &Point3<f64>) -> (usize, f64, f64) {
+        let mut closest = (0, 0., 0.);
+
+        self.bvh.closest(position, |index, closest_distance| {
+            let triangle = self.triangle(index);
+            let (u, v) = triangle.closest_barycentric(position);
+
+            let distance = (triangle.point_at(u, v) - position).magnitude_squared();
+            (distance < closest_distance).then(|| {
+                closest = (index, u, v);
+                distance
+            })
+        });
+
+        closest
+    }
+
+    fn closest_triangle(&self, ray: &Ray) -> Option<(usize, f64, f64, f64)> {
+        let mut closest = None;
+
+        self.bvh
+            .intersect(ray, f64::INFINITY, |index, closest_distance| {
+                self.triangle(index)
+                    .intersect(ray)
+                    .filter(|(t, _, _)| *t < closest_distance)
+                    .map(|(t, u, v)| {
+                        closest = Some((index, t, u, v));
+                        t
+                    })
+            });
+
+        closest
+    }
+}
+
+impl Shape for TriangleMesh {
+    fn intersection_distance(&self, ray: &Ray) -> Option<f64> {
+        self.closest_triangle(ray).map(|(_, t, _, _)| t)
+    }
+
+    fn sample_normal(&self, position: Point3<f64>) -> Vector3<f64> {
+        let (index, u, v) = self.closest_point(&position);
+        self.interpolated_normal(index, u, v)
+    }
+
+    /// The barycentric coordinates within the triangle that was hit.
+    fn uv(&self, position: Point3<f64>) -> Vector2<f64> {
+        let (_, u, v) = self.closest_point(&position);
+        Vector2::new(u, v)
+    }
+
+    fn sample_random_point(&self, u: Vector2<f64>) -> Point3<f64> {
+        self.sample_surface_point(u).position
+    }
+
+    /// Picks a triangle in proportion to its area and a uniform point on it, the normal and
+    /// texture coordinates come from that triangle.
+    fn sample_surface_point(&self, u: Vector2<f64>) -> ShadingPoint {
+        // The position within the bin of the picked triangle is uniform again.
+        let (x, _, index) = self.area_distribution.sample_at(u.x);
+        let remapped = x * self.area_distribution.len() as f64 - index as f64;
+        let (u, v) = Triangle::sample_barycentric(Vector2::new(remapped.clamp(0., 1.), u.y));
+        ShadingPoint {
+            position: self.triangle(index).point_at(u, v),
+            normal: self.interpolated_normal(index, u, v),
+            uv: Vector2::new(u, v),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounding_box
+    }
+
+    fn blocks(&self, ray: &Ray, max_distance: f64) -> bool {
+        self.bvh.any_hit(ray, max_distance, |index, max_distance| {
+            self.triangle(index)
+                .intersect(ray)
+                .is_some_and(|(t, _, _)| t < max_distance)
+        })
+    }
+
+    fn intersection(&self, ray: &Ray) -> Option<IntersectionInfo> {
+        self.closest_triangle(ray)
+            .map(|(index, distance, u, v)| IntersectionInfo {
+                distance,
+                position: ray.sample(distance),
+                normal: self.interpolated_normal(index, u, v),
+                uv: Vector2::new(u, v),
+            })
+    }
+}
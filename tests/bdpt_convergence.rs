@@ -0,0 +1,241 @@
+use std::f64::consts::TAU;
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    bsdf::Dielectric,
+    camera::CameraSettings,
+    filter::GaussianFilter,
+    light::{EnvironmentLight, PointLight},
+    object::ObjectDefinition,
+    renderer::{BDPTRenderer, PathTracer, RecursiveBDPT},
+    shape::{Cuboid, Plane},
+    Camera, Material, RenderBuffer, Renderer, Scene, Sphere,
+};
+
+use nalgebra as na;
+
+use na::{Point3, Vector3};
+
+const SIZE: u32 = 16;
+const MAX_BOUNCES: u8 = 5;
+
+/// A pinhole camera looking into the box, so that BDPT also connects light paths directly to
+/// the camera.
+fn camera() -> Camera {
+    let camera_settings = CameraSettings {
+        z: 2.,
+        width: SIZE,
+        height: SIZE,
+        fov_degrees: 70.,
+        znear: 1.,
+        ..Default::default()
+    };
+    Camera::new(camera_settings, PinholeAperture, 2.25)
+}
+
+/// The walls and the block of a small diffuse Cornell box, without a light. `open` leaves out
+/// the ceiling and the side walls to let in light from the environment.
+fn cornell_box(open: bool) -> Vec<ObjectDefinition> {
+    let white = Material::new_lambertian(Vector3::new(0.8, 0.8, 0.8));
+    let wall = |material: Material, x, y, z, rx, ry| ObjectDefinition {
+        shape: Box::new(Plane::new(2., 2.)),
+        material,
+        x,
+        y,
+        z,
+        rx,
+        ry,
+        ..Default::default()
+    };
+
+    let mut objects = vec![
+        wall(white.clone(), 0., -1., 0., TAU / 4., 0.),
+        wall(white.clone(), 0., 0., -1., 0., 0.),
+        ObjectDefinition {
+            shape: Box::new(Cuboid::new(0.6, 0.6, 0.6)),
+            material: white.clone(),
+            x: -0.25,
+            y: -0.7,
+            z: -0.2,
+            ry: TAU / 10.,
+            ..Default::default()
+        },
+    ];
+    if open {
+        return objects;
+    }
+
+    objects.extend([
+        wall(white, 0., 1., 0., TAU / 4., 0.),
+        wall(
+            Material::new_lambertian(Vector3::new(0.8, 0.1, 0.1)),
+            -1.,
+            0.,
+            0.,
+            0.,
+            TAU / 4.,
+        ),
+        wall(
+            Material::new_lambertian(Vector3::new(0.1, 0.8, 0.1)),
+            1.,
+            0.,
+            0.,
+            0.,
+            TAU / 4.,
+        ),
+    ]);
+    objects
+}
+
+/// An area light just below the ceiling of the box.
+fn ceiling_light() -> ObjectDefinition {
+    ObjectDefinition {
+        shape: Box::new(Plane::new(0.5, 0.5)),
+        material: Material::new_emissive(Vector3::new(1., 1., 1.), 10.),
+        y: 0.99,
+        rx: TAU / 4.,
+        ..Default::default()
+    }
+}
+
+/// A smooth glass sphere on the floor of the box.
+fn dielectric_sphere() -> ObjectDefinition {
+    ObjectDefinition {
+        shape: Box::new(Sphere::new(0.3)),
+        material: Material::new_bsdf(Dielectric::new(1.5, 0.)),
+        x: 0.4,
+        y: -0.7,
+        z: 0.2,
+        ..Default::default()
+    }
+}
+
+/// The Cornell box lit by its ceiling light, with `extra` objects added.
+fn lit_cornell_box(camera: Camera, extra: Vec<ObjectDefinition>) -> Scene {
+    let mut objects = cornell_box(false);
+    objects.push(ceiling_light());
+    objects.extend(extra);
+    Scene::new(camera, objects)
+}
+
+fn mean(render_buffer: &RenderBuffer) -> Vector3<f64> {
+    let mut sum = Vector3::zeros();
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            sum += render_buffer[(x, y)];
+        }
+    }
+    sum / (SIZE * SIZE) as f64
+}
+
+/// The average over all pixels of the absolute difference between the images.
+fn mean_difference(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let mut sum = 0.;
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            sum += (a[(x, y)] - b[(x, y)]).abs().sum() / 3.;
+        }
+    }
+    sum / (SIZE * SIZE) as f64
+}
+
+/// Checks that `bidirectional` matches a path traced render of `scene`.
+fn assert_matches_path_tracer(scene: &Scene, bidirectional: &RenderBuffer) {
+    let path_traced = PathTracer::new(MAX_BOUNCES)
+        .parallel(256)
+        .with_seed(1)
+        .render(scene);
+
+    let reference = mean(&path_traced);
+    let bias = (mean(bidirectional) - reference).abs().max() / reference.max();
+    let difference = mean_difference(&path_traced, bidirectional) / reference.max();
+
+    assert!(bias < 0.02, "the image means differ by {bias}");
+    assert!(difference < 0.1, "pixels differ by {difference} on average");
+}
+
+fn assert_converges(scene: &Scene) {
+    let bidirectional = BDPTRenderer::new(MAX_BOUNCES)
+        .parallel(64)
+        .with_seed(2)
+        .render(scene);
+    assert_matches_path_tracer(scene, &bidirectional);
+}
+
+#[test]
+fn bdpt_converges_to_path_tracer() {
+    assert_converges(&lit_cornell_box(camera(), Vec::new()));
+}
+
+/// The recursive variant ends both camera and light paths with Russian roulette, and has to
+/// share paths through the sphere between fewer strategies.
+#[test]
+fn recursive_bdpt_converges_to_path_tracer() {
+    let scene = lit_cornell_box(camera(), vec![dielectric_sphere()]);
+    let bidirectional = RecursiveBDPT::new(MAX_BOUNCES)
+        .with_roulette_depth(2)
+        .parallel(1024)
+        .with_seed(2)
+        .render(&scene);
+    assert_matches_path_tracer(&scene, &bidirectional);
+}
+
+/// Light paths connected to the camera are spread over several pixels by wide filters.
+#[test]
+fn bdpt_converges_with_gaussian_filter() {
+    assert_converges(&lit_cornell_box(
+        camera().with_filter(GaussianFilter::default()),
+        Vec::new(),
+    ));
+}
+
+/// Specular vertices can't be connected to, so the paths through the sphere rely on the
+/// densities of the vertices around them.
+#[test]
+fn bdpt_converges_with_dielectric_sphere() {
+    assert_converges(&lit_cornell_box(camera(), vec![dielectric_sphere()]));
+}
+
+/// Point and spot lights can't be hit, they are only found by connecting to them and by light
+/// paths starting at them.
+#[test]
+fn bdpt_converges_with_point_lights() {
+    let lights = vec![
+        PointLight::new(Point3::new(0., 0.7, 0.), Vector3::new(1., 1., 1.), 2.).into(),
+        PointLight::spot(
+            Point3::new(0.6, 0.8, 0.4),
+            Vector3::new(-0.5, -1., -0.3),
+            Vector3::new(1., 0.8, 0.6),
+            3.,
+            0.3,
+            0.5,
+        )
+        .into(),
+    ];
+    assert_converges(&Scene::with_lights(camera(), cornell_box(false), lights));
+}
+
+/// Camera paths that leave the open box find the environment, and light paths start from it.
+#[test]
+fn bdpt_converges_with_environment() {
+    // A dim sky with a bright patch up and to the left, so the environment is importance
+    // sampled unevenly.
+    let (width, height) = (16, 8);
+    let pixels = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            if (5..7).contains(&x) && (1..3).contains(&y) {
+                Vector3::new(20., 18., 15.)
+            } else {
+                Vector3::new(0.2, 0.3, 0.5)
+            }
+        })
+        .collect();
+    let environment = EnvironmentLight::new(width, height, pixels);
+
+    assert_converges(&Scene::with_lights(
+        camera(),
+        cornell_box(true),
+        vec![environment.into()],
+    ));
+}
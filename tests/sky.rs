@@ -0,0 +1,98 @@
+use std::f64::consts::{PI, TAU};
+
+use path_tracer::{
+    aperture::PinholeAperture,
+    camera::CameraSettings,
+    light::{Light, PreethamSky},
+    object::ObjectDefinition,
+    renderer::BDPTRenderer,
+    shape::Plane,
+    Camera, Material, RenderBuffer, Renderer, Scene,
+};
+
+use nalgebra as na;
+
+use na::Vector3;
+
+const SIZE: u32 = 8;
+const ALBEDO: f64 = 0.8;
+const ELEVATION_DEGREES: f64 = 60.;
+
+/// A diffuse floor under a clear glass roof, seen from under the roof by a camera looking
+/// straight down. Sunlight only reaches the floor through the roof.
+fn glass_roof(lights: Vec<Light>) -> Scene {
+    let camera_settings = CameraSettings {
+        y: 0.3,
+        rx: -TAU / 4.,
+        width: SIZE,
+        height: SIZE,
+        fov_degrees: 60.,
+        znear: 0.1,
+        ..Default::default()
+    };
+    let camera = Camera::new(camera_settings, PinholeAperture, 1.);
+
+    let objects = vec![
+        ObjectDefinition {
+            shape: Box::new(Plane::new(3., 3.)),
+            material: Material::new_lambertian(Vector3::repeat(ALBEDO)),
+            y: -0.5,
+            rx: TAU / 4.,
+            ..Default::default()
+        },
+        ObjectDefinition {
+            shape: Box::new(Plane::new(3., 3.)),
+            material: Material::new_reflective(Vector3::new(1., 1., 1.), 0., 1., 1.),
+            y: 0.5,
+            rx: TAU / 4.,
+            ..Default::default()
+        },
+    ];
+
+    Scene::with_lights(camera, objects, lights)
+}
+
+fn mean(render_buffer: &RenderBuffer) -> Vector3<f64> {
+    let mut sum = Vector3::zeros();
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            sum += render_buffer[(x, y)];
+        }
+    }
+    sum / (SIZE * SIZE) as f64
+}
+
+#[test]
+fn sun_shines_through_glass() {
+    let elevation = ELEVATION_DEGREES.to_radians();
+    let sky = PreethamSky::new(elevation, TAU / 10., 3.);
+
+    let renderer = BDPTRenderer::new(3).parallel(512).with_seed(1);
+    let sunlit = mean(&renderer.render(&glass_roof(sky.lights())));
+    let sky_only = mean(&renderer.render(&glass_roof(vec![sky.environment(64, 32).into()])));
+
+    // The sun adds the light of a single bounce off the floor.
+    let expected = sky.sun_irradiance() * elevation.sin() * ALBEDO / PI;
+    let sunlight = sunlit - sky_only;
+    let error = (sunlight - expected).abs().max() / expected.max();
+
+    assert!(sky_only.max() > 0., "the sky doesn't light the floor");
+    assert!(
+        error < 0.1,
+        "the sun adds {sunlight:?} instead of {expected:?}"
+    );
+}
+
+#[test]
+fn sun_is_seen_in_the_background() {
+    let sky = PreethamSky::new(ELEVATION_DEGREES.to_radians(), TAU / 10., 3.);
+    let scene = glass_roof(sky.lights());
+
+    let sun = scene.background(&sky.sun_direction());
+    let next_to_sun = scene.background(&(sky.sun_direction() + Vector3::new(0.1, 0., 0.)));
+
+    assert!(
+        sun.max() > 100. * next_to_sun.max(),
+        "the sun disk is {sun:?} against a sky of {next_to_sun:?}"
+    );
+}